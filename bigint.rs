@@ -0,0 +1,83 @@
+// Arbitrary-precision integers, kept as little-endian bytes and encoded as unbounded LEB128.
+
+#[cfg(feature = "text-writer")]
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use super::*;
+
+pub(crate) fn trim_uint(le: &[u8]) -> &[u8] {
+    let len = le.iter().rposition(|byte| *byte != 0).map_or(0, |i| i + 1);
+    &le[..len]
+}
+
+pub(crate) fn is_negative(le: &[u8]) -> bool {
+    le.last().is_some_and(|byte| byte & 0x80 != 0)
+}
+
+pub(crate) fn trim_int(le: &[u8]) -> &[u8] {
+    let negative = is_negative(le);
+    let pad = if negative { 0xFF } else { 0 };
+    let mut len = le.len();
+    // a padding byte is redundant if the byte below has the same sign, or if it is the only one and zero
+    while len > 0 && le[len - 1] == pad {
+        let redundant = match len {
+            1 => !negative,
+            _ => is_negative(&le[..len - 1]) == negative,
+        };
+        if !redundant {
+            break;
+        }
+        len -= 1;
+    }
+    &le[..len]
+}
+
+// numeric order of magnitudes
+pub(crate) fn cmp_uint(a: &[u8], b: &[u8]) -> Ordering {
+    let (a, b) = (trim_uint(a), trim_uint(b));
+    a.len().cmp(&b.len()).then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+// numeric order of two's complements, where of the same sign a longer one is further from zero
+pub(crate) fn cmp_int(a: &[u8], b: &[u8]) -> Ordering {
+    let negative = is_negative(a);
+    if negative != is_negative(b) {
+        return if negative { Ordering::Less } else { Ordering::Greater };
+    }
+    let (a, b) = (trim_int(a), trim_int(b));
+    let by_len = if negative { b.len().cmp(&a.len()) } else { a.len().cmp(&b.len()) };
+    by_len.then_with(|| a.iter().rev().cmp(b.iter().rev()))
+}
+
+// position of the highest bit differing from the sign plus one
+pub(crate) fn bit_len(le: &[u8], negative: bool) -> usize {
+    let pad = if negative { 0xFF } else { 0 };
+    le.iter().rposition(|byte| *byte != pad)
+        .map_or(0, |i| i * 8 + 8 - (le[i] ^ pad).leading_zeros() as usize)
+}
+
+// magnitude of a negative two's complement
+#[cfg(feature = "text-writer")]
+pub(crate) fn negated(le: &[u8]) -> Vec<u8> {
+    let mut carry = true;
+    let mut magnitude: Vec<u8> = le.iter().map(|byte| {
+        let (byte, overflow) = (!byte).overflowing_add(carry as u8);
+        carry = overflow;
+        byte
+    }).collect();
+    let len = trim_uint(&magnitude).len();
+    magnitude.truncate(len);
+    magnitude
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    /// From a little-endian magnitude of any length.
+    pub fn big_uint(le: &[u8]) -> Value<B> {
+        Value::BigUint(trim_uint(le).into())
+    }
+
+    /// From a little-endian two's complement of any length.
+    pub fn big_int(le: &[u8]) -> Value<B> {
+        Value::BigInt(trim_int(le).into())
+    }
+}
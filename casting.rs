@@ -16,12 +16,16 @@ impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
             Uint
             Int
             Bool
+            BigUint
+            BigInt
             Uints
             String
+            Utf8
             Bytes
             Tuple
             List
             Option
+            Map
             Alias
             Enum
             Choice
@@ -55,9 +59,12 @@ impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
                 Uint
                 Int
                 Bool
+                BigUint
+                BigInt
                 Uints
                 Bytes
                 String
+                Utf8
                 Type
                 TypeId
             }
@@ -76,6 +83,9 @@ impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
             Value::Tuple(values) => {
                 Type::Tuple(values.iter().map(|value| value.as_type()).collect())
             }
+            Value::Map(key_type, value_type, _) => {
+                Type::Map(Box::new(key_type.clone()), Box::new(value_type.clone()))
+            }
         }
     }
 }
@@ -100,8 +110,11 @@ impl Type {
                 Uint
                 Int
                 Bool
+                BigUint
+                BigInt
                 Uints
                 String
+                Utf8
                 Bytes
                 Type
                 TypeId
@@ -110,10 +123,12 @@ impl Type {
                 Tuple
                 List
                 Option
+                Map
                 Alias
                 Enum
                 Choice
                 Struct
+                Param
             }
         }
     }
@@ -139,6 +154,7 @@ impl TypeId {
             }
             non_empty {
                 Std
+                ThirdParty
             }
         }
     }
@@ -0,0 +1,157 @@
+// Checks values against the definitions of a registry, with generics instantiated.
+
+use alloc::vec::Vec;
+use super::{*, schema::*};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CheckError {
+    UnknownTypeId(TypeId),
+    DefKindMismatch { type_id: TypeId, tag: Tag },
+    GenericsLen { type_id: TypeId, len: usize, exp_len: usize },
+    FieldsLen { type_id: TypeId, len: usize, exp_len: usize },
+    UnknownVariant { type_id: TypeId, variant_id: VariantId },
+    // definition refers to a parameter out of its generics
+    UnboundParam(TypeId),
+    TypeMismatch { r#type: Type, exp_type: Type },
+    /// Payload of a fixed-width integer alias out of its range.
+    OutOfRange(TypeId),
+}
+
+pub(crate) type Result<T> = core::result::Result<T, CheckError>;
+
+impl Registry {
+    fn def_of(&self, type_id: &TypeId, generics: &[Type]) -> Result<&Def> {
+        let def = self.get(type_id).ok_or_else(|| CheckError::UnknownTypeId(type_id.clone()))?;
+        if generics.len() != def.generics.len() {
+            return Err(CheckError::GenericsLen { type_id: type_id.clone(), len: generics.len(), exp_len: def.generics.len() });
+        }
+        Ok(def)
+    }
+
+    fn instantiate(r#type: &Type, type_id: &TypeId, generics: &[Type]) -> Result<Type> {
+        r#type.substitute(generics).ok_or_else(|| CheckError::UnboundParam(type_id.clone()))
+    }
+
+    fn unify(r#type: &Type, exp_type: &Type) -> Result<Type> {
+        Type::unify(r#type, exp_type)
+            .ok_or_else(|| CheckError::TypeMismatch { r#type: r#type.clone(), exp_type: exp_type.clone() })
+    }
+
+    // the type an alias stands for
+    pub(crate) fn alias_type(&self, type_id: &TypeId, generics: &[Type]) -> Result<Type> {
+        let DefKind::Alias(r#type) = &self.def_of(type_id, generics)?.kind else {
+            return Err(CheckError::DefKindMismatch { type_id: type_id.clone(), tag: Tag::Alias });
+        };
+        Self::instantiate(r#type, type_id, generics)
+    }
+
+    pub(crate) fn check_enum_variant(&self, type_id: &TypeId, variant_id: VariantId) -> Result<()> {
+        let DefKind::Enum(variants) = &self.def_of(type_id, &[])?.kind else {
+            return Err(CheckError::DefKindMismatch { type_id: type_id.clone(), tag: Tag::Enum });
+        };
+        if variants.iter().any(|variant| variant.id == variant_id) {
+            Ok(())
+        } else {
+            Err(CheckError::UnknownVariant { type_id: type_id.clone(), variant_id })
+        }
+    }
+
+    // the type of a choice variant
+    pub(crate) fn choice_variant_type(&self, type_id: &TypeId, generics: &[Type], variant_id: VariantId) -> Result<Type> {
+        let DefKind::Choice(variants) = &self.def_of(type_id, generics)?.kind else {
+            return Err(CheckError::DefKindMismatch { type_id: type_id.clone(), tag: Tag::Choice });
+        };
+        let variant = variants.iter().find(|variant| variant.id == variant_id)
+            .ok_or_else(|| CheckError::UnknownVariant { type_id: type_id.clone(), variant_id })?;
+        Self::instantiate(&variant.r#type, type_id, generics)
+    }
+
+    // the types of the fields of a struct
+    pub(crate) fn struct_field_types(&self, type_id: &TypeId, generics: &[Type]) -> Result<Vec<Type>> {
+        let DefKind::Struct(fields) = &self.def_of(type_id, generics)?.kind else {
+            return Err(CheckError::DefKindMismatch { type_id: type_id.clone(), tag: Tag::Struct });
+        };
+        fields.iter().map(|field| Self::instantiate(&field.r#type, type_id, generics)).collect()
+    }
+
+    /// Checks `value` and everything nested in it against the registered definitions.
+    /// Values with `TypeId::Anonymous` have no definition, only their contents are checked.
+    pub fn check<B: AsRef<[u8]> + ByteStorage>(&self, value: &Value<B>) -> Result<()> {
+        match value {
+            Value::Uint(_)
+            | Value::Int(_)
+            | Value::Bool(_)
+            | Value::BigUint(_)
+            | Value::BigInt(_)
+            | Value::Uints(_)
+            | Value::Bytes(_)
+            | Value::String(_)
+            | Value::Utf8(_)
+            | Value::Type(_)
+            | Value::TypeId(_) => Ok(()),
+
+            Value::Tuple(values) => values.iter().try_for_each(|value| self.check(value)),
+            Value::List(r#type, values) => values.iter().try_for_each(|value| self.check_as(value, r#type)),
+            Value::Option(r#type, value) => value.as_deref().map_or(Ok(()), |value| self.check_as(value, r#type)),
+            Value::Map(key_type, value_type, entries) => entries.iter().try_for_each(|(key, value)| {
+                self.check_as(key, key_type)?;
+                self.check_as(value, value_type)
+            }),
+
+            Value::Alias(TypeId::Anonymous, _, value)
+            | Value::Choice(TypeId::Anonymous, _, _, value) => self.check(value),
+            Value::Enum(TypeId::Anonymous, _) => Ok(()),
+            Value::Struct(TypeId::Anonymous, _, values) => values.iter().try_for_each(|value| self.check(value)),
+
+            Value::Alias(type_id, generics, payload) => {
+                let r#type = self.alias_type(type_id, generics)?;
+                // fixed-width integers are range checked as the readers do
+                if !well_known::in_range(value) {
+                    return Err(CheckError::OutOfRange(type_id.clone()));
+                }
+                self.check_as(payload, &r#type)
+            }
+            Value::Enum(type_id, variant_id) => self.check_enum_variant(type_id, *variant_id),
+            Value::Choice(type_id, generics, variant_id, value) => {
+                self.check_as(value, &self.choice_variant_type(type_id, generics, *variant_id)?)
+            }
+            Value::Struct(type_id, generics, values) => {
+                let types = self.struct_field_types(type_id, generics)?;
+                if values.len() != types.len() {
+                    return Err(CheckError::FieldsLen { type_id: type_id.clone(), len: values.len(), exp_len: types.len() });
+                }
+                values.iter().zip(types.iter()).try_for_each(|(value, r#type)| self.check_as(value, r#type))
+            }
+        }
+    }
+
+    /// Checks that `value` is assignable to `exp_type`, then checks it as `check` does.
+    pub fn check_as<B: AsRef<[u8]> + ByteStorage>(&self, value: &Value<B>, exp_type: &Type) -> Result<()> {
+        match (value, exp_type) {
+            (Value::Tuple(values), Type::Tuple(types)) if values.len() == types.len() => {
+                values.iter().zip(types.iter()).try_for_each(|(value, r#type)| self.check_as(value, r#type))
+            }
+            (Value::List(r#type, values), Type::List(exp_type2)) => {
+                let r#type = Self::unify(r#type, exp_type2)?;
+                values.iter().try_for_each(|value| self.check_as(value, &r#type))
+            }
+            (Value::Option(r#type, value), Type::Option(exp_type2)) => {
+                let r#type = Self::unify(r#type, exp_type2)?;
+                value.as_deref().map_or(Ok(()), |value| self.check_as(value, &r#type))
+            }
+            (Value::Map(key_type, value_type, entries), Type::Map(exp_key_type, exp_value_type)) => {
+                let key_type = Self::unify(key_type, exp_key_type)?;
+                let value_type = Self::unify(value_type, exp_value_type)?;
+                entries.iter().try_for_each(|(key, value)| {
+                    self.check_as(key, &key_type)?;
+                    self.check_as(value, &value_type)
+                })
+            }
+            (_, Type::Unknown) => self.check(value),
+            (value, exp_type) => {
+                let _ = Self::unify(&value.as_type(), exp_type)?;
+                self.check(value)
+            }
+        }
+    }
+}
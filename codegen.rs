@@ -0,0 +1,415 @@
+// Generates Rust types and `convert` impls from schema definitions.
+// Meant to be called from a build script, writing the result into `OUT_DIR` and `include!`-ing it.
+
+use alloc::{string::String, vec::Vec, format};
+use super::{*, schema::*};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CodegenError {
+    UnknownTypeId(TypeId),
+    UnboundParam(u32),
+    /// Names that are not Rust identifiers, or cannot be one even as raw identifiers, and the
+    /// names of the fields and variants the generated code adds.
+    InvalidName(Box<str>),
+    /// Tuples longer than the ones `convert` is implemented for.
+    TupleLen(usize),
+}
+
+type Result<T> = core::result::Result<T, CodegenError>;
+
+pub struct Options<'a> {
+    /// Path of this crate as seen from the generated code.
+    pub crate_path: &'a str,
+    /// Byte storage the generated types use for `Bytes` and `Unknown`. Needs to be `Ord`, as the
+    /// generated types are so that they can be map keys.
+    pub bytes_type: &'a str,
+}
+
+pub const DEFAULT_OPTIONS: Options<'static> = Options {
+    crate_path: "bcbc",
+    bytes_type: "Vec<u8>",
+};
+
+const KEYWORDS: &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn",
+    "else", "enum", "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop",
+    "macro", "match", "mod", "move", "mut", "override", "priv", "pub", "ref", "return", "static", "struct",
+    "trait", "true", "try", "type", "typeof", "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+// keywords that cannot be raw identifiers
+const NON_RAW_KEYWORDS: &[&str] = &["_", "crate", "self", "Self", "super"];
+
+// the field and variant holding the generics a definition does not use
+const MARKER_FIELD: &str = "_marker";
+const MARKER_VARIANT: &str = "_Marker";
+
+// the longest tuples `convert` is implemented for
+const MAX_TUPLE_LEN: usize = 8;
+
+fn check_name(name: &str) -> Result<()> {
+    let valid = name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        && !NON_RAW_KEYWORDS.contains(&name);
+    if valid { Ok(()) } else { Err(CodegenError::InvalidName(name.into())) }
+}
+
+// names are checked by `check_name` before
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("r#{name}")
+    } else {
+        name.into()
+    }
+}
+
+// calls `f` with `r#type` and the types in it, except those behind a `Vec` or map if `by_value`
+fn walk<F: FnMut(&Type)>(r#type: &Type, by_value: bool, f: &mut F) {
+    f(r#type);
+    match r#type {
+        Type::Tuple(types) | Type::Alias(_, types) | Type::Choice(_, types) | Type::Struct(_, types) => {
+            for r#type in types {
+                walk(r#type, by_value, f);
+            }
+        }
+        Type::Option(r#type) => walk(r#type, by_value, f),
+        Type::List(r#type) if !by_value => walk(r#type, by_value, f),
+        Type::Map(key_type, value_type) if !by_value => {
+            walk(key_type, by_value, f);
+            walk(value_type, by_value, f);
+        }
+        _ => {}
+    }
+}
+
+fn def_types(def: &Def) -> Vec<&Type> {
+    match &def.kind {
+        DefKind::Alias(r#type) => Vec::from([r#type]),
+        DefKind::Enum(_) => Vec::new(),
+        DefKind::Choice(variants) => variants.iter().map(|variant| &variant.r#type).collect(),
+        DefKind::Struct(fields) => fields.iter().map(|field| &field.r#type).collect(),
+    }
+}
+
+fn type_id_expr(krate: &str, type_id: &TypeId) -> String {
+    match type_id {
+        TypeId::Anonymous => format!("{krate}::TypeId::Anonymous"),
+        TypeId::Std(id) => format!("{krate}::TypeId::Std({id:#x})"),
+        TypeId::ThirdParty(namespace, id) => format!("{krate}::TypeId::ThirdParty({namespace:#x}, {id:#x})"),
+    }
+}
+
+struct Generator<'a> {
+    registry: &'a Registry,
+    options: &'a Options<'a>,
+    // the definition being generated and its generics
+    type_id: &'a TypeId,
+    params: &'a [Box<str>],
+    out: String,
+}
+
+impl<'a> Generator<'a> {
+    fn def_name(&self, type_id: &TypeId) -> Result<&'a str> {
+        self.registry.get(type_id).map(|def| &*def.name).ok_or_else(|| CodegenError::UnknownTypeId(type_id.clone()))
+    }
+
+    // whether `to` is held by value by `from`, or by what `from` holds by value
+    fn reaches(&self, from: &TypeId, to: &TypeId) -> bool {
+        let mut visited = Vec::new();
+        let mut stack = Vec::from([from.clone()]);
+        while let Some(type_id) = stack.pop() {
+            if &type_id == to {
+                return true;
+            }
+            if visited.contains(&type_id) {
+                continue;
+            }
+            for r#type in self.registry.get(&type_id).map(def_types).unwrap_or_default() {
+                walk(r#type, true, &mut |r#type| match r#type {
+                    Type::Alias(type_id, _) | Type::Choice(type_id, _) | Type::Struct(type_id, _) => stack.push(type_id.clone()),
+                    _ => {}
+                });
+            }
+            visited.push(type_id);
+        }
+        false
+    }
+
+    fn rust_types(&self, types: &[Type], indirect: bool) -> Result<Vec<String>> {
+        types.iter().map(|r#type| self.rust_type(r#type, indirect)).collect()
+    }
+
+    // types that would contain the definition being generated are boxed, unless `indirect`,
+    // that is already behind a `Vec` or map
+    fn rust_type(&self, r#type: &Type, indirect: bool) -> Result<String> {
+        let Options { crate_path: krate, bytes_type } = self.options;
+        Ok(match r#type {
+            Type::Unknown => format!("{krate}::Value<{bytes_type}>"),
+            Type::Uint => "u128".into(),
+            Type::Int => "i128".into(),
+            Type::Bool => "bool".into(),
+            Type::BigUint => format!("{krate}::convert::BigUint"),
+            Type::BigInt => format!("{krate}::convert::BigInt"),
+            Type::Uints => "Box<[u128]>".into(),
+            Type::Bytes => format!("{krate}::convert::Bytes<{bytes_type}>"),
+            Type::String => "String".into(),
+            Type::Utf8 => format!("{krate}::convert::Utf8<{bytes_type}>"),
+            Type::Tuple(types) => {
+                if types.len() > MAX_TUPLE_LEN {
+                    return Err(CodegenError::TupleLen(types.len()));
+                }
+                let mut s = String::from("(");
+                for r#type in self.rust_types(types, indirect)? {
+                    s.push_str(&r#type);
+                    s.push_str(", ");
+                }
+                s.push(')');
+                s
+            }
+            Type::List(r#type) => format!("Vec<{}>", self.rust_type(r#type, true)?),
+            Type::Option(r#type) => format!("Option<{}>", self.rust_type(r#type, indirect)?),
+            Type::Map(key_type, value_type) => {
+                format!("std::collections::BTreeMap<{}, {}>", self.rust_type(key_type, true)?, self.rust_type(value_type, true)?)
+            }
+            Type::Enum(type_id) => ident(self.def_name(type_id)?),
+            Type::Alias(type_id, generics)
+            | Type::Choice(type_id, generics)
+            | Type::Struct(type_id, generics) => {
+                if let Type::Alias(..) = r#type
+                    && let Some(native) = well_known::native_int(type_id)
+                    && generics.is_empty()
+                {
+                    return Ok(native.into());
+                }
+                let name = ident(self.def_name(type_id)?);
+                let name = if generics.is_empty() {
+                    name
+                } else {
+                    format!("{name}<{}>", self.rust_types(generics, indirect)?.join(", "))
+                };
+                if !indirect && self.reaches(type_id, self.type_id) {
+                    format!("Box<{name}>")
+                } else {
+                    name
+                }
+            }
+            Type::Type => format!("{krate}::Type"),
+            Type::TypeId => format!("{krate}::TypeId"),
+            Type::Param(index) => {
+                let param = self.params.get(*index as usize).ok_or(CodegenError::UnboundParam(*index))?;
+                ident(param)
+            }
+        })
+    }
+
+    // `<T: bound, ...>` or empty
+    fn params_decl(&self, bound: Option<&str>) -> String {
+        if self.params.is_empty() {
+            return String::new();
+        }
+        let params = self.params.iter().map(|param| match bound {
+            Some(bound) => format!("{}: {bound}", ident(param)),
+            None => ident(param),
+        });
+        format!("<{}>", params.collect::<Vec<_>>().join(", "))
+    }
+
+    fn push(&mut self, s: &str) {
+        self.out.push_str(s);
+        self.out.push('\n');
+    }
+
+    fn def(&mut self, def: &'a Def) -> Result<()> {
+        check_name(&def.name)?;
+        for param in def.generics.iter() {
+            check_name(param)?;
+        }
+        match &def.kind {
+            DefKind::Alias(_) => {}
+            DefKind::Enum(variants) => variants.iter().try_for_each(|variant| check_name(&variant.name))?,
+            DefKind::Choice(variants) => variants.iter().try_for_each(|variant| match &*variant.name {
+                MARKER_VARIANT => Err(CodegenError::InvalidName(variant.name.clone())),
+                name => check_name(name),
+            })?,
+            DefKind::Struct(fields) => fields.iter().try_for_each(|field| match &*field.name {
+                MARKER_FIELD => Err(CodegenError::InvalidName(field.name.clone())),
+                name => check_name(name),
+            })?,
+        }
+        self.type_id = &def.type_id;
+        // enum values have no generics
+        self.params = match def.kind {
+            DefKind::Enum(_) => &[],
+            _ => &def.generics[..],
+        };
+        // params the definition does not use, held by a `PhantomData`
+        let mut used = Vec::from_iter(self.params.iter().map(|_| false));
+        for r#type in def_types(def) {
+            walk(r#type, false, &mut |r#type| {
+                if let Type::Param(index) = r#type && let Some(used) = used.get_mut(*index as usize) {
+                    *used = true;
+                }
+            });
+        }
+        let unused: String = self.params.iter().zip(used)
+            .filter(|(_, used)| !used)
+            .map(|(param, _)| format!("{}, ", ident(param)))
+            .collect();
+        let marker = (!unused.is_empty()).then(|| format!("core::marker::PhantomData<({unused})>"));
+        let krate = self.options.crate_path;
+        let bytes_type = self.options.bytes_type;
+        let type_id = type_id_expr(krate, &def.type_id);
+        let generics = {
+            let mut s = String::from("Box::new([");
+            for param in self.params.iter() {
+                s.push_str(&format!("{}::value_type(), ", ident(param)));
+            }
+            s.push_str("])");
+            s
+        };
+        let from_bound = format!("{krate}::convert::FromValue<{bytes_type}>");
+        let into_bound = format!("{krate}::convert::IntoValue<{bytes_type}>");
+        let name = format!("{}{}", ident(&def.name), self.params_decl(None));
+        let from_impl = format!("impl{} {from_bound} for {name}", self.params_decl(Some(&from_bound)));
+        let from_sig = format!("fn from_value(value: {krate}::Value<{bytes_type}>) -> Result<Self, {krate}::convert::ConvertError>");
+        let into_impl = format!("impl{} {into_bound} for {name}", self.params_decl(Some(&into_bound)));
+        let into_sig = format!("fn into_value(self) -> {krate}::Value<{bytes_type}>");
+        let from = format!("{krate}::convert::FromValue::from_value");
+        let into = format!("{krate}::convert::IntoValue::<{bytes_type}>::into_value");
+
+        match &def.kind {
+            DefKind::Alias(r#type) => {
+                let inner = self.rust_type(r#type, false)?;
+                let (marker_field, marker_value) = match &marker {
+                    Some(marker) => (format!(", pub {marker}"), ", core::marker::PhantomData"),
+                    None => (String::new(), ""),
+                };
+                self.push("#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]");
+                self.push(&format!("pub struct {name}(pub {inner}{marker_field});"));
+                self.push(&format!("{from_impl} {{"));
+                self.push(&format!("    {from_sig} {{"));
+                self.push(&format!("        Ok(Self({from}({krate}::convert::alias_value(value, &{type_id})?)?{marker_value}))"));
+                self.push("    }");
+                self.push("}");
+                self.push(&format!("{into_impl} {{"));
+                self.push(&format!("    fn value_type() -> {krate}::Type {{ {krate}::Type::Alias({type_id}, {generics}) }}"));
+                self.push(&format!("    {into_sig} {{"));
+                self.push(&format!("        {krate}::Value::Alias({type_id}, {generics}, Box::new({into}(self.0)))"));
+                self.push("    }");
+                self.push("}");
+            }
+            DefKind::Enum(variants) => {
+                self.push("#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]");
+                self.push(&format!("pub enum {name} {{"));
+                for variant in variants {
+                    self.push(&format!("    {},", ident(&variant.name)));
+                }
+                self.push("}");
+                self.push(&format!("{from_impl} {{"));
+                self.push(&format!("    {from_sig} {{"));
+                self.push(&format!("        match {krate}::convert::enum_variant(value, &{type_id})? {{"));
+                for variant in variants {
+                    self.push(&format!("            {:#x} => Ok(Self::{}),", variant.id, ident(&variant.name)));
+                }
+                self.push(&format!("            id => Err({krate}::convert::ConvertError::UnknownVariant(id)),"));
+                self.push("        }");
+                self.push("    }");
+                self.push("}");
+                self.push(&format!("{into_impl} {{"));
+                self.push(&format!("    fn value_type() -> {krate}::Type {{ {krate}::Type::Enum({type_id}) }}"));
+                self.push(&format!("    {into_sig} {{"));
+                self.push("        let id = match self {");
+                for variant in variants {
+                    self.push(&format!("            Self::{} => {:#x},", ident(&variant.name), variant.id));
+                }
+                self.push("        };");
+                self.push(&format!("        {krate}::Value::Enum({type_id}, id)"));
+                self.push("    }");
+                self.push("}");
+            }
+            DefKind::Choice(variants) => {
+                self.push("#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]");
+                self.push(&format!("pub enum {name} {{"));
+                for variant in variants {
+                    self.push(&format!("    {}({}),", ident(&variant.name), self.rust_type(&variant.r#type, false)?));
+                }
+                if let Some(marker) = &marker {
+                    self.push("    #[doc(hidden)]");
+                    self.push(&format!("    {MARKER_VARIANT}(core::convert::Infallible, {marker}),"));
+                }
+                self.push("}");
+                self.push(&format!("{from_impl} {{"));
+                self.push(&format!("    {from_sig} {{"));
+                self.push(&format!("        let (id, value) = {krate}::convert::choice_payload(value, &{type_id})?;"));
+                self.push("        match id {");
+                for variant in variants {
+                    self.push(&format!("            {:#x} => Ok(Self::{}({from}(value)?)),", variant.id, ident(&variant.name)));
+                }
+                self.push(&format!("            id => Err({krate}::convert::ConvertError::UnknownVariant(id)),"));
+                self.push("        }");
+                self.push("    }");
+                self.push("}");
+                self.push(&format!("{into_impl} {{"));
+                self.push(&format!("    fn value_type() -> {krate}::Type {{ {krate}::Type::Choice({type_id}, {generics}) }}"));
+                self.push(&format!("    {into_sig} {{"));
+                self.push("        let (id, value) = match self {");
+                for variant in variants {
+                    self.push(&format!("            Self::{}(value) => ({:#x}, {into}(value)),", ident(&variant.name), variant.id));
+                }
+                if marker.is_some() {
+                    self.push(&format!("            Self::{MARKER_VARIANT}(never, _) => match never {{}},"));
+                }
+                self.push("        };");
+                self.push(&format!("        {krate}::Value::Choice({type_id}, {generics}, id, Box::new(value))"));
+                self.push("    }");
+                self.push("}");
+            }
+            DefKind::Struct(fields) => {
+                self.push("#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]");
+                self.push(&format!("pub struct {name} {{"));
+                for field in fields {
+                    self.push(&format!("    pub {}: {},", ident(&field.name), self.rust_type(&field.r#type, false)?));
+                }
+                if let Some(marker) = &marker {
+                    self.push(&format!("    pub {MARKER_FIELD}: {marker},"));
+                }
+                self.push("}");
+                let vars = (0..fields.len()).map(|i| format!("f{i}")).collect::<Vec<_>>().join(", ");
+                self.push(&format!("{from_impl} {{"));
+                self.push(&format!("    {from_sig} {{"));
+                self.push(&format!("        let [{vars}] = {krate}::convert::struct_fields::<_, {}>(value, &{type_id})?;", fields.len()));
+                self.push(&format!("        Ok(Self {{"));
+                for (i, field) in fields.iter().enumerate() {
+                    self.push(&format!("            {}: {from}(f{i})?,", ident(&field.name)));
+                }
+                if marker.is_some() {
+                    self.push(&format!("            {MARKER_FIELD}: core::marker::PhantomData,"));
+                }
+                self.push("        })");
+                self.push("    }");
+                self.push("}");
+                self.push(&format!("{into_impl} {{"));
+                self.push(&format!("    fn value_type() -> {krate}::Type {{ {krate}::Type::Struct({type_id}, {generics}) }}"));
+                self.push(&format!("    {into_sig} {{"));
+                self.push(&format!("        {krate}::Value::Struct({type_id}, {generics}, Box::new(["));
+                for field in fields {
+                    self.push(&format!("            {into}(self.{}),", ident(&field.name)));
+                }
+                self.push("        ]))");
+                self.push("    }");
+                self.push("}");
+            }
+        }
+        self.push("");
+        Ok(())
+    }
+}
+
+pub fn generate(registry: &Registry, options: &Options) -> Result<String> {
+    let mut generator = Generator { registry, options, type_id: &TypeId::Anonymous, params: &[], out: String::new() };
+    // fixed-width integers map to the native ones instead
+    for def in registry.iter().filter(|def| well_known::native_int(&def.type_id).is_none()) {
+        generator.def(def)?;
+    }
+    Ok(generator.out)
+}
@@ -0,0 +1,185 @@
+// Untagged encoding of values whose type both sides know. Only payloads are written,
+// laid out by the type and the definitions it refers to:
+//
+// - `Uint`, `Int`, `Bool`, `BigUint`, `BigInt`, `Bytes`, `String` and `Utf8` as after their tags,
+//   `Uints` always in the plain encoding
+// - `Tuple` and `Struct` as their items without a length
+// - `List` and `Map` as a length and the items, `Option` as a bool and the item if there is one
+// - `Alias` as the aliased type, fixed-width integers checked for their range, `Enum` as the variant id, `Choice` as the variant id and the variant type
+// - `Unknown`, `Type` and `TypeId` tagged as in the self-describing encoding
+
+use super::{*, byte_storage::{Input, Output}, check::{self, CheckError}, reader::{Reader, alloc_seq}, schema::Registry, writer::{Writer, Sink}};
+
+impl<S: Sink> Writer<S> {
+    fn s_value<B: AsRef<[u8]> + ByteStorage>(&mut self, registry: &Registry, value: &Value<B>, r#type: &Type) -> check::Result<()> {
+        match (value, r#type) {
+            (value, Type::Unknown) => self.value(value),
+            (Value::Uint(n), Type::Uint) => self.uleb128(*n),
+            (Value::Int(n), Type::Int) => self.sleb128(*n),
+            (Value::Bool(n), Type::Bool) => self.uleb128(*n as u8),
+            (Value::BigUint(le), Type::BigUint) => self.uleb128_big(le),
+            (Value::BigInt(le), Type::BigInt) => self.sleb128_big(le),
+            (Value::Uints(uints), Type::Uints) => {
+                self.uleb128(uints.len());
+                for n in uints {
+                    self.uleb128(*n);
+                }
+            }
+            (Value::Bytes(bytes), Type::Bytes) | (Value::Utf8(bytes), Type::Utf8) => {
+                self.uleb128(bytes.as_ref().len());
+                self.bytes(bytes);
+            }
+            (Value::String(chars), Type::String) => {
+                self.uleb128(chars.len());
+                for char in chars {
+                    self.uleb128(*char as u32);
+                }
+            }
+            (Value::Tuple(values), Type::Tuple(types)) if values.len() == types.len() => {
+                for (value, r#type) in values.iter().zip(types.iter()) {
+                    self.s_value(registry, value, r#type)?;
+                }
+            }
+            (Value::List(_, values), Type::List(item_type)) => {
+                self.uleb128(values.len());
+                for value in values {
+                    self.s_value(registry, value, item_type)?;
+                }
+            }
+            (Value::Option(_, value), Type::Option(item_type)) => {
+                self.uleb128(value.is_some() as u8);
+                if let Some(value) = value {
+                    self.s_value(registry, value, item_type)?;
+                }
+            }
+            (Value::Map(_, _, entries), Type::Map(key_type, value_type)) => {
+                self.uleb128(entries.len());
+                for (key, value) in ord::sorted_entries(entries, Value::canonical_cmp) {
+                    self.s_value(registry, key, key_type)?;
+                    self.s_value(registry, value, value_type)?;
+                }
+            }
+            // fixed-width integers out of range are rejected by `check_as` before
+            (Value::Alias(type_id, _, payload), Type::Alias(exp_type_id, generics)) if type_id == exp_type_id => {
+                self.s_value(registry, payload, &registry.alias_type(type_id, generics)?)?;
+            }
+            (Value::Enum(type_id, variant_id), Type::Enum(exp_type_id)) if type_id == exp_type_id => {
+                self.uleb128(*variant_id);
+            }
+            (Value::Choice(type_id, _, variant_id, value), Type::Choice(exp_type_id, generics)) if type_id == exp_type_id => {
+                let r#type = registry.choice_variant_type(type_id, generics, *variant_id)?;
+                self.uleb128(*variant_id);
+                self.s_value(registry, value, &r#type)?;
+            }
+            (Value::Struct(type_id, _, values), Type::Struct(exp_type_id, generics)) if type_id == exp_type_id => {
+                let types = registry.struct_field_types(type_id, generics)?;
+                if values.len() != types.len() {
+                    return Err(CheckError::FieldsLen { type_id: type_id.clone(), len: values.len(), exp_len: types.len() });
+                }
+                for (value, r#type) in values.iter().zip(types.iter()) {
+                    self.s_value(registry, value, r#type)?;
+                }
+            }
+            (Value::Type(r#type), Type::Type) => self.v_type(r#type),
+            (Value::TypeId(type_id), Type::TypeId) => self.v_type_id(type_id),
+            (value, r#type) => {
+                return Err(CheckError::TypeMismatch { r#type: value.as_type(), exp_type: r#type.clone() });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<'_, I> {
+    fn s_value(&mut self, registry: &Registry, r#type: &Type) -> Result<Value<B>> {
+        Ok(match r#type {
+            Type::Unknown => self.value()?,
+            Type::Uint => Value::Uint(self.c_uint()?),
+            Type::Int => Value::Int(self.c_int()?),
+            Type::Bool => Value::Bool(self.c_bool()?),
+            Type::BigUint => Value::BigUint(self.c_big_uint()?),
+            Type::BigInt => Value::BigInt(self.c_big_int()?),
+            Type::Uints => Value::Uints(self.c_uints()?),
+            Type::Bytes => Value::Bytes(self.c_bytes()?),
+            Type::String => Value::String(self.c_string()?),
+            Type::Utf8 => Value::Utf8(self.c_utf8()?),
+            Type::Tuple(types) => {
+                Value::Tuple(types.iter().map(|r#type| self.s_value(registry, r#type)).collect::<Result<_>>()?)
+            }
+            Type::List(item_type) => {
+                let len = self.h_list_items()?;
+                let values = alloc_seq(len, |_| self.s_value(registry, item_type))?;
+                Value::List((**item_type).clone(), values)
+            }
+            Type::Option(item_type) => {
+                let value = if self.c_bool()? { Some(Box::new(self.s_value(registry, item_type)?)) } else { None };
+                Value::Option((**item_type).clone(), value)
+            }
+            Type::Map(key_type, value_type) => {
+                let len = self.h_map_entries()?;
+                let entries = alloc_seq(len, |_| {
+                    let key = self.s_value(registry, key_type)?;
+                    let value = self.s_value(registry, value_type)?;
+                    Ok((key, value))
+                })?;
+                for pair in entries.windows(2) {
+                    match pair[0].0.canonical_cmp(&pair[1].0) {
+                        core::cmp::Ordering::Less => {}
+                        core::cmp::Ordering::Equal => return Err(Error::DuplicateMapKey),
+                        core::cmp::Ordering::Greater => return Err(Error::UnorderedMapKeys),
+                    }
+                }
+                Value::Map((**key_type).clone(), (**value_type).clone(), entries)
+            }
+            Type::Alias(type_id, generics) => {
+                let r#type = registry.alias_type(type_id, generics)?;
+                let value = match self.p_fixed_width(type_id, generics, false)? {
+                    Some(value) => value,
+                    None => self.s_value(registry, &r#type)?,
+                };
+                Value::Alias(type_id.clone(), generics.clone(), Box::new(value))
+            }
+            Type::Enum(type_id) => {
+                let variant_id = self.c_uint()?;
+                registry.check_enum_variant(type_id, variant_id)?;
+                Value::Enum(type_id.clone(), variant_id)
+            }
+            Type::Choice(type_id, generics) => {
+                let variant_id = self.c_uint()?;
+                let value = self.s_value(registry, &registry.choice_variant_type(type_id, generics, variant_id)?)?;
+                Value::Choice(type_id.clone(), generics.clone(), variant_id, Box::new(value))
+            }
+            Type::Struct(type_id, generics) => {
+                let types = registry.struct_field_types(type_id, generics)?;
+                let values = types.iter().map(|r#type| self.s_value(registry, r#type)).collect::<Result<_>>()?;
+                Value::Struct(type_id.clone(), generics.clone(), values)
+            }
+            Type::Type => Value::Type(self.v_type()?),
+            Type::TypeId => Value::TypeId(self.v_type_id()?),
+            Type::Param(index) => return Err(Error::ParamInSchema(*index)),
+        })
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    /// Encodes only the payloads of the value, laid out by `r#type` and the definitions in `registry`.
+    /// Not self-describing, decoded by `decode_with_schema` with the same type and definitions.
+    pub fn encode_with_schema<O: Output>(&self, r#type: &Type, registry: &Registry) -> core::result::Result<O::Storage, CheckError> {
+        registry.check_as(self, r#type)?;
+        let mut writer = Writer::<O>::new();
+        writer.s_value(registry, self, r#type)?;
+        Ok(writer.into_inner())
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    pub fn decode_with_schema_with_max_lens<I: Input<Storage = B>>(buf: B, r#type: &Type, registry: &Registry, max_lens: MaxLens) -> FullResult<Value<B>, B> {
+        let mut reader = Reader::<I>::new(buf, max_lens);
+        let val = reader.s_value(registry, r#type);
+        reader.finish_with(val)
+    }
+
+    pub fn decode_with_schema<I: Input<Storage = B>>(buf: B, r#type: &Type, registry: &Registry) -> FullResult<Value<B>, B> {
+        Self::decode_with_schema_with_max_lens::<I>(buf, r#type, registry, DEFAULT_MAX_LENS)
+    }
+}
@@ -0,0 +1,175 @@
+// Compares two versions of a definition, reporting whether data written by one can be read by the other.
+// Fields are positional on the wire, so they are matched by index; variants are matched by id.
+
+use alloc::vec::Vec;
+use super::{*, schema::*};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    TypeIdChanged { old: TypeId, new: TypeId },
+    KindChanged,
+    GenericsLenChanged { old: usize, new: usize },
+    AliasTypeChanged { old: Type, new: Type },
+
+    FieldAppended { index: usize, name: Box<str>, has_default: bool },
+    FieldRemoved { index: usize, name: Box<str>, has_default: bool },
+    FieldRenamed { index: usize, old: Box<str>, new: Box<str> },
+    FieldMoved { name: Box<str>, old_index: usize, new_index: usize },
+    FieldTypeChanged { index: usize, old: Type, new: Type },
+
+    VariantAdded { id: VariantId, name: Box<str> },
+    VariantRemoved { id: VariantId, name: Box<str> },
+    VariantRenamed { id: VariantId, old: Box<str>, new: Box<str> },
+    VariantIdChanged { name: Box<str>, old: VariantId, new: VariantId },
+    VariantTypeChanged { id: VariantId, old: Type, new: Type },
+}
+
+impl Change {
+    /// Whether readers of the old definition can read data written with the new one, decoding
+    /// tolerantly as `Tolerance` does: missing fields count as readable if they have a default,
+    /// and appended ones as readable since they are kept with `keep_unknown_fields`.
+    pub fn old_reads_new(&self) -> bool {
+        match self {
+            Change::FieldRemoved { has_default, .. } => *has_default,
+            Change::FieldAppended { .. }
+            | Change::FieldRenamed { .. }
+            | Change::VariantRenamed { .. }
+            | Change::VariantRemoved { .. } => true,
+            Change::AliasTypeChanged { old, new }
+            | Change::FieldTypeChanged { old, new, .. }
+            | Change::VariantTypeChanged { old, new, .. } => new.is_assignable_to(old),
+            _ => false,
+        }
+    }
+
+    /// Whether readers of the new definition can read data written with the old one.
+    pub fn new_reads_old(&self) -> bool {
+        match self {
+            Change::FieldAppended { has_default, .. } => *has_default,
+            Change::FieldRenamed { .. }
+            | Change::VariantRenamed { .. }
+            | Change::VariantAdded { .. } => true,
+            Change::AliasTypeChanged { old, new }
+            | Change::FieldTypeChanged { old, new, .. }
+            | Change::VariantTypeChanged { old, new, .. } => old.is_assignable_to(new),
+            _ => false,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Report {
+    pub changes: Vec<Change>,
+}
+
+impl Report {
+    pub fn is_unchanged(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    pub fn old_reads_new(&self) -> bool {
+        self.changes.iter().all(Change::old_reads_new)
+    }
+
+    pub fn new_reads_old(&self) -> bool {
+        self.changes.iter().all(Change::new_reads_old)
+    }
+
+    pub fn is_compatible(&self) -> bool {
+        self.old_reads_new() && self.new_reads_old()
+    }
+}
+
+fn compare_fields(old: &[Field], new: &[Field], changes: &mut Vec<Change>) {
+    for (index, (old_field, new_field)) in old.iter().zip(new.iter()).enumerate() {
+        if old_field.name != new_field.name {
+            match old.iter().position(|field| field.name == new_field.name) {
+                Some(old_index) => changes.push(Change::FieldMoved { name: new_field.name.clone(), old_index, new_index: index }),
+                None => changes.push(Change::FieldRenamed { index, old: old_field.name.clone(), new: new_field.name.clone() }),
+            }
+        }
+        if old_field.r#type != new_field.r#type {
+            changes.push(Change::FieldTypeChanged { index, old: old_field.r#type.clone(), new: new_field.r#type.clone() });
+        }
+    }
+    for (index, field) in new.iter().enumerate().skip(old.len()) {
+        changes.push(Change::FieldAppended { index, name: field.name.clone(), has_default: field.default.is_some() });
+    }
+    for (index, field) in old.iter().enumerate().skip(new.len()) {
+        changes.push(Change::FieldRemoved { index, name: field.name.clone(), has_default: field.default.is_some() });
+    }
+}
+
+// (id, name, payload type)
+fn enum_variants(variants: &[EnumVariant]) -> impl Iterator<Item = (VariantId, &str, Option<&Type>)> + Clone {
+    variants.iter().map(|variant| (variant.id, &*variant.name, None))
+}
+
+fn choice_variants(variants: &[ChoiceVariant]) -> impl Iterator<Item = (VariantId, &str, Option<&Type>)> + Clone {
+    variants.iter().map(|variant| (variant.id, &*variant.name, Some(&variant.r#type)))
+}
+
+fn compare_variants<'a>(
+    old: impl Iterator<Item = (VariantId, &'a str, Option<&'a Type>)> + Clone,
+    new: impl Iterator<Item = (VariantId, &'a str, Option<&'a Type>)> + Clone,
+    changes: &mut Vec<Change>,
+) {
+    for (id, name, r#type) in new.clone() {
+        match old.clone().find(|old| old.0 == id) {
+            Some((_, old_name, old_type)) => {
+                if old_name != name {
+                    changes.push(Change::VariantRenamed { id, old: old_name.into(), new: name.into() });
+                }
+                if let (Some(old_type), Some(r#type)) = (old_type, r#type) && old_type != r#type {
+                    changes.push(Change::VariantTypeChanged { id, old: old_type.clone(), new: r#type.clone() });
+                }
+            }
+            None => match old.clone().find(|old| old.1 == name) {
+                Some((old_id, ..)) => changes.push(Change::VariantIdChanged { name: name.into(), old: old_id, new: id }),
+                None => changes.push(Change::VariantAdded { id, name: name.into() }),
+            },
+        }
+    }
+    for (id, name, _) in old {
+        if !new.clone().any(|new| new.0 == id || new.1 == name) {
+            changes.push(Change::VariantRemoved { id, name: name.into() });
+        }
+    }
+}
+
+pub fn compare(old: &Def, new: &Def) -> Report {
+    let mut changes = Vec::new();
+    if old.type_id != new.type_id {
+        changes.push(Change::TypeIdChanged { old: old.type_id.clone(), new: new.type_id.clone() });
+    }
+    if old.generics.len() != new.generics.len() {
+        changes.push(Change::GenericsLenChanged { old: old.generics.len(), new: new.generics.len() });
+    }
+    match (&old.kind, &new.kind) {
+        (DefKind::Alias(old), DefKind::Alias(new)) => {
+            if old != new {
+                changes.push(Change::AliasTypeChanged { old: old.clone(), new: new.clone() });
+            }
+        }
+        (DefKind::Enum(old), DefKind::Enum(new)) => {
+            compare_variants(enum_variants(old), enum_variants(new), &mut changes);
+        }
+        (DefKind::Choice(old), DefKind::Choice(new)) => {
+            compare_variants(choice_variants(old), choice_variants(new), &mut changes);
+        }
+        (DefKind::Struct(old), DefKind::Struct(new)) => compare_fields(old, new, &mut changes),
+        _ => changes.push(Change::KindChanged),
+    }
+    Report { changes }
+}
+
+/// Compares every definition present in both registries, leaving out the unchanged ones.
+pub fn compare_registries(old: &Registry, new: &Registry) -> Vec<(TypeId, Report)> {
+    old.iter()
+        .filter_map(|old_def| {
+            let new_def = new.get(&old_def.type_id)?;
+            let report = compare(old_def, new_def);
+            (!report.is_unchanged()).then(|| (old_def.type_id.clone(), report))
+        })
+        .collect()
+}
@@ -0,0 +1,421 @@
+// Conversions between `Value<B>` and plain Rust types. Generated code (see `codegen`) builds on these.
+
+use alloc::{vec::Vec, string::String, collections::BTreeMap};
+use super::*;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ConvertError {
+    Tag { tag: Tag, exp_tag: Tag },
+    TypeId { type_id: TypeId, exp_type_id: TypeId },
+    Len { len: usize, exp_len: usize },
+    UnknownVariant(VariantId),
+    /// Payload of a fixed-width integer alias out of its range.
+    OutOfRange(TypeId),
+}
+
+type Result<T> = core::result::Result<T, ConvertError>;
+
+pub trait FromValue<B: AsRef<[u8]> + ByteStorage>: Sized {
+    fn from_value(value: Value<B>) -> Result<Self>;
+}
+
+pub trait IntoValue<B: AsRef<[u8]> + ByteStorage> {
+    fn value_type() -> Type;
+
+    fn into_value(self) -> Value<B>;
+}
+
+/// Bytes payload, distinguished from other `B` uses.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Bytes<B>(pub B);
+
+/// UTF-8 string payload, see `Value::Utf8`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct Utf8<B>(pub B);
+
+impl<B: AsRef<[u8]>> Utf8<B> {
+    pub fn as_str(&self) -> core::result::Result<&str, core::str::Utf8Error> {
+        core::str::from_utf8(self.0.as_ref())
+    }
+}
+
+/// Little-endian magnitude, see `Value::BigUint`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BigUint(pub Box<[u8]>);
+
+/// Little-endian two's complement, see `Value::BigInt`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct BigInt(pub Box<[u8]>);
+
+impl From<u128> for BigUint {
+    fn from(n: u128) -> Self {
+        BigUint(bigint::trim_uint(&n.to_le_bytes()).into())
+    }
+}
+
+impl From<i128> for BigInt {
+    fn from(n: i128) -> Self {
+        BigInt(bigint::trim_int(&n.to_le_bytes()).into())
+    }
+}
+
+fn tag_err<T, B: AsRef<[u8]> + ByteStorage>(value: &Value<B>, exp_tag: Tag) -> Result<T> {
+    Err(ConvertError::Tag { tag: value.as_tag(), exp_tag })
+}
+
+fn check_type_id(type_id: TypeId, exp_type_id: &TypeId) -> Result<()> {
+    if &type_id == exp_type_id {
+        Ok(())
+    } else {
+        Err(ConvertError::TypeId { type_id, exp_type_id: exp_type_id.clone() })
+    }
+}
+
+fn fixed_len<T, const N: usize>(items: Box<[T]>) -> Result<[T; N]> {
+    let len = items.len();
+    let items: Box<[T; N]> = items.try_into().map_err(|_| ConvertError::Len { len, exp_len: N })?;
+    Ok(*items)
+}
+
+pub fn tuple_items<B: AsRef<[u8]> + ByteStorage, const N: usize>(value: Value<B>) -> Result<[Value<B>; N]> {
+    match value {
+        Value::Tuple(items) => fixed_len(items),
+        value => tag_err(&value, Tag::Tuple),
+    }
+}
+
+pub fn alias_value<B: AsRef<[u8]> + ByteStorage>(value: Value<B>, exp_type_id: &TypeId) -> Result<Value<B>> {
+    match value {
+        Value::Alias(type_id, _, value) => {
+            check_type_id(type_id, exp_type_id)?;
+            Ok(*value)
+        }
+        value => tag_err(&value, Tag::Alias),
+    }
+}
+
+pub fn enum_variant<B: AsRef<[u8]> + ByteStorage>(value: Value<B>, exp_type_id: &TypeId) -> Result<VariantId> {
+    match value {
+        Value::Enum(type_id, variant_id) => {
+            check_type_id(type_id, exp_type_id)?;
+            Ok(variant_id)
+        }
+        value => tag_err(&value, Tag::Enum),
+    }
+}
+
+pub fn choice_payload<B: AsRef<[u8]> + ByteStorage>(value: Value<B>, exp_type_id: &TypeId) -> Result<(VariantId, Value<B>)> {
+    match value {
+        Value::Choice(type_id, _, variant_id, value) => {
+            check_type_id(type_id, exp_type_id)?;
+            Ok((variant_id, *value))
+        }
+        value => tag_err(&value, Tag::Choice),
+    }
+}
+
+pub fn struct_fields<B: AsRef<[u8]> + ByteStorage, const N: usize>(value: Value<B>, exp_type_id: &TypeId) -> Result<[Value<B>; N]> {
+    match value {
+        Value::Struct(type_id, _, fields) => {
+            check_type_id(type_id, exp_type_id)?;
+            fixed_len(fields)
+        }
+        value => tag_err(&value, Tag::Struct),
+    }
+}
+
+macro_rules! impl_direct {
+    ($($ty:ty => $name:ident,)*) => {$(
+        impl<B: AsRef<[u8]> + ByteStorage> FromValue<B> for $ty {
+            fn from_value(value: Value<B>) -> Result<Self> {
+                match value {
+                    Value::$name(inner) => Ok(inner),
+                    value => tag_err(&value, Tag::$name),
+                }
+            }
+        }
+
+        impl<B: AsRef<[u8]> + ByteStorage> IntoValue<B> for $ty {
+            fn value_type() -> Type {
+                Type::$name
+            }
+
+            fn into_value(self) -> Value<B> {
+                Value::$name(self)
+            }
+        }
+    )*};
+}
+
+impl_direct! {
+    u128 => Uint,
+    i128 => Int,
+    bool => Bool,
+    Box<[u128]> => Uints,
+    Type => Type,
+    TypeId => TypeId,
+}
+
+// fixed-width integers are the aliases in `well_known`
+macro_rules! impl_fixed_width {
+    ($($ty:ty => $type_id:ident $name:ident,)*) => {$(
+        impl<B: AsRef<[u8]> + ByteStorage> FromValue<B> for $ty {
+            fn from_value(value: Value<B>) -> Result<Self> {
+                match alias_value(value, &well_known::$type_id)? {
+                    Value::$name(n) => n.try_into().map_err(|_| ConvertError::OutOfRange(well_known::$type_id)),
+                    value => tag_err(&value, Tag::$name),
+                }
+            }
+        }
+
+        impl<B: AsRef<[u8]> + ByteStorage> IntoValue<B> for $ty {
+            fn value_type() -> Type {
+                Type::Alias(well_known::$type_id, Box::new([]))
+            }
+
+            fn into_value(self) -> Value<B> {
+                Value::Alias(well_known::$type_id, Box::new([]), Box::new(Value::$name(self.into())))
+            }
+        }
+    )*};
+}
+
+impl_fixed_width! {
+    u8 => U8 Uint,
+    u16 => U16 Uint,
+    u32 => U32 Uint,
+    u64 => U64 Uint,
+    i8 => I8 Int,
+    i16 => I16 Int,
+    i32 => I32 Int,
+    i64 => I64 Int,
+}
+
+macro_rules! impl_big {
+    ($($name:ident $ctor:ident,)*) => {$(
+        impl<B: AsRef<[u8]> + ByteStorage> FromValue<B> for $name {
+            fn from_value(value: Value<B>) -> Result<Self> {
+                match value {
+                    Value::$name(le) => Ok($name(le)),
+                    value => tag_err(&value, Tag::$name),
+                }
+            }
+        }
+
+        impl<B: AsRef<[u8]> + ByteStorage> IntoValue<B> for $name {
+            fn value_type() -> Type {
+                Type::$name
+            }
+
+            fn into_value(self) -> Value<B> {
+                Value::$ctor(&self.0)
+            }
+        }
+    )*};
+}
+
+impl_big! {
+    BigUint big_uint,
+    BigInt big_int,
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> FromValue<B> for Bytes<B> {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        match value {
+            Value::Bytes(bytes) => Ok(Bytes(bytes)),
+            value => tag_err(&value, Tag::Bytes),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> IntoValue<B> for Bytes<B> {
+    fn value_type() -> Type {
+        Type::Bytes
+    }
+
+    fn into_value(self) -> Value<B> {
+        Value::Bytes(self.0)
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> FromValue<B> for Utf8<B> {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        match value {
+            Value::Utf8(bytes) => Ok(Utf8(bytes)),
+            value => tag_err(&value, Tag::Utf8),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> IntoValue<B> for Utf8<B> {
+    fn value_type() -> Type {
+        Type::Utf8
+    }
+
+    fn into_value(self) -> Value<B> {
+        Value::Utf8(self.0)
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    /// A `Utf8` value of `bytes` if they are valid UTF-8.
+    pub fn utf8(bytes: B) -> core::result::Result<Value<B>, core::str::Utf8Error> {
+        let _ = core::str::from_utf8(bytes.as_ref())?;
+        Ok(Value::Utf8(bytes))
+    }
+
+    /// The text of a `Utf8` value, `None` for other values or invalid UTF-8.
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Utf8(bytes) => core::str::from_utf8(bytes.as_ref()).ok(),
+            _ => None,
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> FromValue<B> for String {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        match value {
+            Value::String(chars) => Ok(chars.iter().collect()),
+            value => tag_err(&value, Tag::String),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> IntoValue<B> for String {
+    fn value_type() -> Type {
+        Type::String
+    }
+
+    fn into_value(self) -> Value<B> {
+        Value::String(self.chars().collect())
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, T: FromValue<B>> FromValue<B> for Vec<T> {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        match value {
+            Value::List(_, items) => items.into_vec().into_iter().map(T::from_value).collect(),
+            value => tag_err(&value, Tag::List),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, T: IntoValue<B>> IntoValue<B> for Vec<T> {
+    fn value_type() -> Type {
+        Type::List(Box::new(T::value_type()))
+    }
+
+    fn into_value(self) -> Value<B> {
+        Value::List(T::value_type(), self.into_iter().map(T::into_value).collect())
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, T: FromValue<B>> FromValue<B> for Option<T> {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        match value {
+            Value::Option(_, value) => value.map(|value| T::from_value(*value)).transpose(),
+            value => tag_err(&value, Tag::Option),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, T: IntoValue<B>> IntoValue<B> for Option<T> {
+    fn value_type() -> Type {
+        Type::Option(Box::new(T::value_type()))
+    }
+
+    fn into_value(self) -> Value<B> {
+        Value::Option(T::value_type(), self.map(|value| Box::new(value.into_value())))
+    }
+}
+
+// generated types containing themselves hold that boxed
+impl<B: AsRef<[u8]> + ByteStorage, T: FromValue<B>> FromValue<B> for Box<T> {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        T::from_value(value).map(Box::new)
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, T: IntoValue<B>> IntoValue<B> for Box<T> {
+    fn value_type() -> Type {
+        T::value_type()
+    }
+
+    fn into_value(self) -> Value<B> {
+        (*self).into_value()
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, K: FromValue<B> + Ord, V: FromValue<B>> FromValue<B> for BTreeMap<K, V> {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        match value {
+            Value::Map(_, _, entries) => entries.into_vec().into_iter()
+                .map(|(key, value)| Ok((K::from_value(key)?, V::from_value(value)?)))
+                .collect(),
+            value => tag_err(&value, Tag::Map),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, K: IntoValue<B>, V: IntoValue<B>> IntoValue<B> for BTreeMap<K, V> {
+    fn value_type() -> Type {
+        Type::Map(Box::new(K::value_type()), Box::new(V::value_type()))
+    }
+
+    // the order of `K` may differ from the canonical one
+    fn into_value(self) -> Value<B> {
+        Value::map(K::value_type(), V::value_type(), self.into_iter().map(|(key, value)| (key.into_value(), value.into_value())))
+    }
+}
+
+// dynamically typed, used for `Type::Unknown`
+impl<B: AsRef<[u8]> + ByteStorage> FromValue<B> for Value<B> {
+    fn from_value(value: Value<B>) -> Result<Self> {
+        Ok(value)
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> IntoValue<B> for Value<B> {
+    fn value_type() -> Type {
+        Type::Unknown
+    }
+
+    fn into_value(self) -> Value<B> {
+        self
+    }
+}
+
+macro_rules! impl_tuple {
+    ($($len:literal: ($($ty:ident $var:ident)*))*) => {$(
+        impl<B: AsRef<[u8]> + ByteStorage, $($ty: FromValue<B>),*> FromValue<B> for ($($ty,)*) {
+            fn from_value(value: Value<B>) -> Result<Self> {
+                let [$($var),*] = tuple_items::<B, $len>(value)?;
+                Ok(($($ty::from_value($var)?,)*))
+            }
+        }
+
+        impl<B: AsRef<[u8]> + ByteStorage, $($ty: IntoValue<B>),*> IntoValue<B> for ($($ty,)*) {
+            fn value_type() -> Type {
+                Type::Tuple(Box::new([$($ty::value_type()),*]))
+            }
+
+            fn into_value(self) -> Value<B> {
+                let ($($var,)*) = self;
+                Value::Tuple(Box::new([$($var.into_value()),*]))
+            }
+        }
+    )*};
+}
+
+impl_tuple! {
+    0: ()
+    1: (T0 v0)
+    2: (T0 v0 T1 v1)
+    3: (T0 v0 T1 v1 T2 v2)
+    4: (T0 v0 T1 v1 T2 v2 T3 v3)
+    5: (T0 v0 T1 v1 T2 v2 T3 v3 T4 v4)
+    6: (T0 v0 T1 v1 T2 v2 T3 v3 T4 v4 T5 v5)
+    7: (T0 v0 T1 v1 T2 v2 T3 v3 T4 v4 T5 v5 T6 v6)
+    8: (T0 v0 T1 v1 T2 v2 T3 v3 T4 v4 T5 v5 T6 v6 T7 v7)
+}
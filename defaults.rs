@@ -0,0 +1,157 @@
+// Default values of types: zeros of the builtin types, and with a registry, of definitions.
+
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use super::{*, schema::*};
+
+pub(crate) struct Defaults<'a, F> {
+    registry: Option<&'a Registry>,
+    empty_bytes: F,
+    // definitions being defaulted with the sizes of their generics. A definition reentered with
+    // generics no smaller contains itself and has no default, as in `Node { next: Node }` or
+    // `S<T> { x: S<l<T>> }`, while `Wrap<Wrap<u>>` only nests
+    path: Vec<(&'a TypeId, usize)>,
+}
+
+// nodes in `types`
+fn size(types: &[Type]) -> usize {
+    types.iter().map(|r#type| 1 + match r#type {
+        Type::Tuple(types)
+        | Type::Alias(_, types)
+        | Type::Choice(_, types)
+        | Type::Struct(_, types) => size(types),
+        Type::List(type2) | Type::Option(type2) => size(core::slice::from_ref(type2)),
+        Type::Map(key_type, value_type) => size(core::slice::from_ref(key_type)) + size(core::slice::from_ref(value_type)),
+        _ => 0,
+    }).sum()
+}
+
+impl<'a, B: AsRef<[u8]> + ByteStorage, E, F: FnMut() -> core::result::Result<B, E>> Defaults<'a, F> {
+    pub(crate) fn new(registry: Option<&'a Registry>, empty_bytes: F) -> Self {
+        Self { registry, empty_bytes, path: Vec::new() }
+    }
+
+    fn seq(&mut self, types: &[Type]) -> core::result::Result<Option<Box<[Value<B>]>>, E> {
+        let mut values = Vec::with_capacity(types.len());
+        for r#type in types {
+            match self.value(r#type)? {
+                Some(value) => values.push(value),
+                None => return Ok(None),
+            }
+        }
+        Ok(Some(values.into_boxed_slice()))
+    }
+
+    fn instantiated(&mut self, r#type: &Type, generics: &[Type]) -> core::result::Result<Option<Value<B>>, E> {
+        match r#type.substitute(generics) {
+            Some(r#type) => self.value(&r#type),
+            None => Ok(None),
+        }
+    }
+
+    /// The declared default of `field`, `None` if there is none.
+    pub(crate) fn field(&mut self, field: &Field, generics: &[Type]) -> core::result::Result<Option<Value<B>>, E> {
+        Ok(match &field.default {
+            None => None,
+            Some(FieldDefault::Zero) => self.instantiated(&field.r#type, generics)?,
+            Some(FieldDefault::Uint(n)) => Some(Value::Uint(*n)),
+            Some(FieldDefault::Int(n)) => Some(Value::Int(*n)),
+            Some(FieldDefault::Bool(b)) => Some(Value::Bool(*b)),
+            Some(FieldDefault::String(chars)) => Some(Value::String(chars.clone())),
+        })
+    }
+
+    fn def(&mut self, def: &'a Def, generics: &[Type]) -> core::result::Result<Option<Value<B>>, E> {
+        let generics_size = size(generics);
+        let reentered = self.path.iter().any(|(type_id, size)| **type_id == def.type_id && *size <= generics_size);
+        if reentered || generics.len() != def.generics.len() {
+            return Ok(None);
+        }
+        self.path.push((&def.type_id, generics_size));
+        let type_id = def.type_id.clone();
+        let value = match &def.kind {
+            DefKind::Alias(r#type) => self.instantiated(r#type, generics)?
+                .map(|value| Value::Alias(type_id, generics.into(), Box::new(value))),
+            DefKind::Enum(variants) => variants.first().map(|variant| Value::Enum(type_id, variant.id)),
+            DefKind::Choice(variants) => match variants.first() {
+                Some(variant) => self.instantiated(&variant.r#type, generics)?
+                    .map(|value| Value::Choice(type_id, generics.into(), variant.id, Box::new(value))),
+                None => None,
+            },
+            DefKind::Struct(fields) => {
+                let mut values = Vec::with_capacity(fields.len());
+                for field in fields {
+                    let value = match self.field(field, generics)? {
+                        Some(value) => Some(value),
+                        None => self.instantiated(&field.r#type, generics)?,
+                    };
+                    match value {
+                        Some(value) => values.push(value),
+                        None => break,
+                    }
+                }
+                (values.len() == fields.len()).then(|| Value::Struct(type_id, generics.into(), values.into_boxed_slice()))
+            }
+        };
+        let _ = self.path.pop();
+        Ok(value)
+    }
+
+    /// The default of `type`, `None` if it has none.
+    pub(crate) fn value(&mut self, r#type: &Type) -> core::result::Result<Option<Value<B>>, E> {
+        Ok(Some(match r#type {
+            Type::Uint => Value::Uint(0),
+            Type::Int => Value::Int(0),
+            Type::Bool => Value::Bool(false),
+            Type::BigUint => Value::BigUint(Box::new([])),
+            Type::BigInt => Value::BigInt(Box::new([])),
+            Type::Uints => Value::Uints(Box::new([])),
+            Type::Bytes => Value::Bytes((self.empty_bytes)()?),
+            Type::String => Value::String(Box::new([])),
+            Type::Utf8 => Value::Utf8((self.empty_bytes)()?),
+            Type::Tuple(types) => match self.seq(types)? {
+                Some(values) => Value::Tuple(values),
+                None => return Ok(None),
+            },
+            Type::List(type2) => Value::List((**type2).clone(), Box::new([])),
+            Type::Option(type2) => Value::Option((**type2).clone(), None),
+            Type::Map(key_type, value_type) => Value::Map((**key_type).clone(), (**value_type).clone(), Box::new([])),
+            Type::Type => Value::Type(Type::Unknown),
+            Type::TypeId => Value::TypeId(TypeId::Anonymous),
+            Type::Alias(type_id, generics)
+            | Type::Choice(type_id, generics)
+            | Type::Struct(type_id, generics) => match self.registry.and_then(|registry| registry.get(type_id)) {
+                Some(def) => return self.def(def, generics),
+                None => return Ok(None),
+            },
+            Type::Enum(type_id) => match self.registry.and_then(|registry| registry.get(type_id)) {
+                Some(def) => return self.def(def, &[]),
+                None => return Ok(None),
+            },
+            Type::Unknown | Type::Param(_) => return Ok(None),
+        }))
+    }
+}
+
+fn infallible<T>(res: core::result::Result<T, Infallible>) -> T {
+    let Ok(value) = res;
+    value
+}
+
+impl<B: AsRef<[u8]> + ByteStorage + Default> Value<B> {
+    /// The default of `type`: `Uint(0)`, `Bool(false)`, empty sequences, `Option(T, None)`,
+    /// `List(T, [])`, tuples of defaults and so on. `None` for `Unknown`, parameters
+    /// and definitions, see `Registry::default_value` for the latter.
+    pub fn default_for(r#type: &Type) -> Option<Value<B>> {
+        infallible(Defaults::new(None, || Ok(B::default())).value(r#type))
+    }
+}
+
+impl Registry {
+    /// Like `Value::default_for`, also defaulting definitions: aliases to the default of their type,
+    /// enums and choices to their first variant, structs to their declared field defaults,
+    /// or the defaults of the field types. Definitions containing themselves have no default.
+    pub fn default_value<B: AsRef<[u8]> + ByteStorage + Default>(&self, r#type: &Type) -> Option<Value<B>> {
+        infallible(Defaults::new(Some(self), || Ok(B::default())).value(r#type))
+    }
+}
@@ -0,0 +1,118 @@
+// Content digests of values, for content-addressed storage and deduplication.
+
+use super::{*, writer::{Writer, Sink}};
+
+/// An incremental hash function, such as an adapter to a SHA-2 or BLAKE3 implementation.
+pub trait Digest: Default {
+    type Output;
+
+    fn update(&mut self, bytes: &[u8]);
+
+    fn finalize(self) -> Self::Output;
+}
+
+struct DigestSink<H>(H);
+
+impl<H: Digest> Sink for DigestSink<H> {
+    #[inline]
+    fn byte(&mut self, byte: u8) {
+        self.0.update(&[byte]);
+    }
+
+    #[inline]
+    fn bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
+        self.0.update(bytes.as_ref());
+    }
+}
+
+// rejects what `decode_canonical` would reject in the encoding of the value, without encoding it
+fn check_canonical<B: AsRef<[u8]> + ByteStorage>(value: &Value<B>) -> Result<()> {
+    match value {
+        Value::Utf8(bytes) => {
+            let _ = core::str::from_utf8(bytes.as_ref()).map_err(Error::Utf8)?;
+        }
+        Value::Tuple(values) | Value::Struct(_, _, values) => {
+            for value in values {
+                check_canonical(value)?;
+            }
+        }
+        Value::List(_, values) => {
+            // items are read with the types `ord::read_type` gives, which must unify
+            let mut types = values.iter().map(ord::read_type);
+            if let Some(first) = types.next() {
+                let _ = types.try_fold(first, |r#type, item_type| {
+                    Type::unify(&r#type, &item_type).ok_or(Error::ListItemTypeMismatch { r#type, item_type })
+                })?;
+            }
+            for value in values {
+                check_canonical(value)?;
+            }
+        }
+        Value::Option(_, value) => {
+            if let Some(value) = value {
+                check_canonical(value)?;
+            }
+        }
+        Value::Map(_, _, entries) => {
+            // entries are written in key order, and read as in `Reader::p_map`
+            let sorted = ord::sorted_entries(entries, Value::read_cmp);
+            let mut types: Option<(Type, Type)> = None;
+            for (index, (key, value)) in sorted.iter().enumerate() {
+                if index > 0 && sorted[index - 1].0.read_cmp(key).is_eq() {
+                    return Err(Error::DuplicateMapKey);
+                }
+                let (entry_key_type, entry_value_type) = (ord::read_type(key), ord::read_type(value));
+                types = Some(match types {
+                    None => (entry_key_type, entry_value_type),
+                    Some((key_type, value_type)) => match (Type::unify(&key_type, &entry_key_type), Type::unify(&value_type, &entry_value_type)) {
+                        (Some(key_type2), Some(value_type2)) => (key_type2, value_type2),
+                        _ => return Err(Error::MapEntryTypeMismatch {
+                            r#type: Type::Map(Box::new(key_type), Box::new(value_type)),
+                            entry_type: Type::Map(Box::new(entry_key_type), Box::new(entry_value_type)),
+                        }),
+                    },
+                });
+                check_canonical(key)?;
+                check_canonical(value)?;
+            }
+        }
+        Value::Alias(_, _, payload) => {
+            // the reader rejects fixed-width integers out of range, in one of several ways
+            if !well_known::in_range(value) {
+                return Err(Error::NonCanonical);
+            }
+            check_canonical(payload)?;
+        }
+        Value::Choice(_, _, _, value) => check_canonical(value)?,
+        Value::Uint(_)
+        | Value::Int(_)
+        | Value::Bool(_)
+        | Value::BigUint(_)
+        | Value::BigInt(_)
+        | Value::Uints(_)
+        | Value::Bytes(_)
+        | Value::String(_)
+        | Value::Enum(..)
+        | Value::Type(_)
+        | Value::TypeId(_) => {}
+    }
+    Ok(())
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    /// `H` over the canonical encoding of the value, see `canonical_encode`, fed to it as it is
+    /// written without a buffer. Values that have none, such as maps with duplicate keys or
+    /// lists whose item types do not unify, are rejected before anything is written.
+    ///
+    /// The digest depends only on the value and `H`: equal values in any representation, such
+    /// as map entries in any order, have the same canonical encoding, which has no
+    /// platform-dependent parts. That includes which of its encodings `Uints` are written in.
+    /// Changing the canonical encoding of existing values is a breaking change of the format,
+    /// so digests stay the same across versions of this crate and can be stored.
+    pub fn digest<H: Digest>(&self) -> Result<H::Output> {
+        check_canonical(self)?;
+        let mut writer = Writer::with_sink(DigestSink(H::default()));
+        writer.value(self);
+        Ok(writer.into_sink().0.finalize())
+    }
+}
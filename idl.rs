@@ -0,0 +1,486 @@
+// A small language for declaring types, e.g.
+//
+//     struct User = 0x1001 <T> { id: u, name: s, tags: l<s>, extra: T }
+//     choice Shape = 0x1002 { 0 Circle(u), 1 Rect(p<u u>) }
+//     enum Color = 0x1003 { 0 Red, 1 Green }
+//     alias Time = 0x1004 b
+//     struct Order = 0xbe:0x01 { id: u }    // third-party id, `namespace:id`
+//     struct Item = 0x1005 { id: u, count: u = 1, note: s = "", flags: n = default }
+//
+// Builtin types are spelled as their type tags (`u i f n b s t d`, `0` for unknown,
+// `l<T>`, `o<T>`, `k<K V>`, `p<T...>`), everything else refers to a declared type by name.
+
+use alloc::{vec::Vec, collections::{BTreeMap, BTreeSet}};
+use super::{*, schema::*};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseErrorKind {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    ExpectedIdent,
+    ExpectedNumber,
+    NumberOverflow,
+    UnknownKind(Box<str>),
+    // a declared name or generic parameter spelled as a builtin type
+    ReservedName(Box<str>),
+    EnumGenerics(Box<str>),
+    UnknownType(Box<str>),
+    GenericsLen { name: Box<str>, len: usize, exp_len: usize },
+    DuplicateName(Box<str>),
+    DuplicateTypeId(TypeId),
+    DuplicateVariantId(VariantId),
+    DuplicateVariantName(Box<str>),
+    DuplicateParam(Box<str>),
+    DuplicateField(Box<str>),
+    UnknownDefault(Box<str>),
+    DefaultTypeMismatch { field: Box<str>, r#type: Type },
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ParseError {
+    pub kind: ParseErrorKind,
+    pub pos: usize,
+}
+
+type Result<T> = core::result::Result<T, ParseError>;
+
+enum RawType<'a> {
+    Resolved(Type),
+    List(Box<RawType<'a>>),
+    Option(Box<RawType<'a>>),
+    Map(Box<RawType<'a>>, Box<RawType<'a>>),
+    Tuple(Vec<RawType<'a>>),
+    Named { name: &'a str, generics: Vec<RawType<'a>>, pos: usize },
+}
+
+enum RawKind<'a> {
+    Alias(RawType<'a>),
+    Enum(Vec<EnumVariant>),
+    Choice(Vec<(VariantId, &'a str, RawType<'a>)>),
+    // name, type, default and its position
+    Struct(Vec<(&'a str, RawType<'a>, Option<(FieldDefault, usize)>)>),
+}
+
+struct RawDef<'a> {
+    name: &'a str,
+    type_id: TypeId,
+    generics: Vec<&'a str>,
+    kind: RawKind<'a>,
+    pos: usize,
+}
+
+fn prim_type(name: &str) -> Option<Type> {
+    Some(match name {
+        "u" => Type::Uint,
+        "i" => Type::Int,
+        "f" => Type::Bool,
+        "v" => Type::BigUint,
+        "w" => Type::BigInt,
+        "n" => Type::Uints,
+        "b" => Type::Bytes,
+        "s" => Type::String,
+        "q" => Type::Utf8,
+        "t" => Type::Type,
+        "d" => Type::TypeId,
+        _ => return None,
+    })
+}
+
+// spelled as a builtin type, which a name or parameter would be shadowed by
+fn is_builtin(name: &str) -> bool {
+    matches!(name, "p" | "l" | "o" | "k") || prim_type(name).is_some()
+}
+
+struct Parser<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn err<T>(&self, kind: ParseErrorKind) -> Result<T> {
+        Err(ParseError { kind, pos: self.pos })
+    }
+
+    fn skip_ws(&mut self) {
+        let src = self.src;
+        loop {
+            let rest = &src[self.pos..];
+            let trimmed = rest.trim_start();
+            self.pos += rest.len() - trimmed.len();
+            if trimmed.starts_with("//") {
+                self.pos += trimmed.find('\n').unwrap_or(trimmed.len());
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn peek(&mut self) -> Option<char> {
+        self.skip_ws();
+        self.src[self.pos..].chars().next()
+    }
+
+    fn eat(&mut self, c: char) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += c.len_utf8();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect(&mut self, c: char) -> Result<()> {
+        match self.peek() {
+            Some(c2) if c2 == c => {
+                self.pos += c.len_utf8();
+                Ok(())
+            }
+            Some(c2) => self.err(ParseErrorKind::UnexpectedChar(c2)),
+            None => self.err(ParseErrorKind::UnexpectedEnd),
+        }
+    }
+
+    fn ident(&mut self) -> Result<&'a str> {
+        if self.peek().is_none() {
+            return self.err(ParseErrorKind::UnexpectedEnd);
+        }
+        let src = self.src;
+        let rest = &src[self.pos..];
+        let len = rest.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(rest.len());
+        if len == 0 || rest.starts_with(|c: char| c.is_ascii_digit()) {
+            return self.err(ParseErrorKind::ExpectedIdent);
+        }
+        self.pos += len;
+        Ok(&rest[..len])
+    }
+
+    fn name(&mut self) -> Result<&'a str> {
+        self.skip_ws();
+        let pos = self.pos;
+        let name = self.ident()?;
+        if is_builtin(name) {
+            return Err(ParseError { kind: ParseErrorKind::ReservedName(name.into()), pos });
+        }
+        Ok(name)
+    }
+
+    fn number(&mut self) -> Result<u128> {
+        if self.peek().is_none() {
+            return self.err(ParseErrorKind::UnexpectedEnd);
+        }
+        let src = self.src;
+        let rest = &src[self.pos..];
+        let (radix, prefix_len) = if rest.starts_with("0x") || rest.starts_with("0X") { (16, 2) } else { (10, 0) };
+        let digits = &rest[prefix_len..];
+        let len = digits.find(|c: char| !(c.is_digit(radix) || c == '_')).unwrap_or(digits.len());
+        if !digits[..len].contains(|c: char| c.is_digit(radix)) {
+            return self.err(ParseErrorKind::ExpectedNumber);
+        }
+        let mut n: u128 = 0;
+        for d in digits[..len].chars().filter_map(|c| c.to_digit(radix)) {
+            n = match n.checked_mul(radix as u128).and_then(|n| n.checked_add(d as u128)) {
+                Some(n) => n,
+                None => return self.err(ParseErrorKind::NumberOverflow),
+            };
+        }
+        self.pos += prefix_len + len;
+        Ok(n)
+    }
+
+    fn type_id(&mut self) -> Result<TypeId> {
+        let id = self.number()?;
+        Ok(if self.eat(':') {
+            TypeId::ThirdParty(id, self.number()?)
+        } else {
+            TypeId::Std(id)
+        })
+    }
+
+    // `default`, `true`, `false`, `"string"` or a number
+    fn default(&mut self) -> Result<FieldDefault> {
+        Ok(match self.peek() {
+            Some('"') => {
+                self.pos += 1;
+                let src = self.src;
+                let rest = &src[self.pos..];
+                let Some(len) = rest.find('"') else {
+                    self.pos = src.len();
+                    return self.err(ParseErrorKind::UnexpectedEnd);
+                };
+                self.pos += len + 1;
+                FieldDefault::String(rest[..len].chars().collect())
+            }
+            Some('-') => {
+                self.pos += 1;
+                let n = self.number()?;
+                match 0i128.checked_sub_unsigned(n) {
+                    Some(n) => FieldDefault::Int(n),
+                    None => return self.err(ParseErrorKind::NumberOverflow),
+                }
+            }
+            Some(c) if c.is_ascii_digit() => FieldDefault::Uint(self.number()?),
+            _ => {
+                let pos = self.pos;
+                match self.ident()? {
+                    "default" => FieldDefault::Zero,
+                    "true" => FieldDefault::Bool(true),
+                    "false" => FieldDefault::Bool(false),
+                    literal => return Err(ParseError { kind: ParseErrorKind::UnknownDefault(literal.into()), pos }),
+                }
+            }
+        })
+    }
+
+    fn seq<T>(&mut self, close: char, sep: Option<char>, mut f: impl FnMut(&mut Self) -> Result<T>) -> Result<Vec<T>> {
+        let mut items = Vec::new();
+        loop {
+            if self.eat(close) {
+                break;
+            }
+            items.push(f(self)?);
+            if let Some(sep) = sep && !self.eat(sep) {
+                self.expect(close)?;
+                break;
+            }
+        }
+        Ok(items)
+    }
+
+    fn r#type(&mut self) -> Result<RawType<'a>> {
+        if self.peek() == Some('0') {
+            self.pos += 1;
+            return Ok(RawType::Resolved(Type::Unknown));
+        }
+        let pos = self.pos;
+        let name = self.ident()?;
+        let generics = if self.eat('<') {
+            self.seq('>', None, |p| p.r#type())?
+        } else {
+            Vec::new()
+        };
+        let exp_len = match name {
+            "p" => return Ok(RawType::Tuple(generics)),
+            "l" | "o" => 1,
+            "k" => 2,
+            name if prim_type(name).is_some() => 0,
+            _ => return Ok(RawType::Named { name, generics, pos }),
+        };
+        if generics.len() != exp_len {
+            return Err(ParseError {
+                kind: ParseErrorKind::GenericsLen { name: name.into(), len: generics.len(), exp_len },
+                pos,
+            });
+        }
+        let mut generics = generics.into_iter();
+        Ok(match (name, generics.next(), generics.next()) {
+            ("l", Some(item), None) => RawType::List(Box::new(item)),
+            ("o", Some(item), None) => RawType::Option(Box::new(item)),
+            ("k", Some(key), Some(value)) => RawType::Map(Box::new(key), Box::new(value)),
+            (name, ..) => RawType::Resolved(prim_type(name).unwrap_or(Type::Unknown)),
+        })
+    }
+
+    fn def(&mut self) -> Result<RawDef<'a>> {
+        self.skip_ws();
+        let kind_pos = self.pos;
+        let kind = self.ident()?;
+        self.skip_ws();
+        let pos = self.pos;
+        let name = self.name()?;
+        self.expect('=')?;
+        let type_id = self.type_id()?;
+        let generics = if self.eat('<') {
+            self.seq('>', None, |p| p.name())?
+        } else {
+            Vec::new()
+        };
+        let kind = match kind {
+            "alias" => RawKind::Alias(self.r#type()?),
+            "enum" => {
+                // enum values hold no other values, so there is nothing to parameterize
+                if !generics.is_empty() {
+                    return Err(ParseError { kind: ParseErrorKind::EnumGenerics(name.into()), pos });
+                }
+                self.expect('{')?;
+                RawKind::Enum(self.seq('}', Some(','), |p| {
+                    let id = p.number()?;
+                    let name = p.ident()?;
+                    Ok(EnumVariant { id, name: name.into() })
+                })?)
+            }
+            "choice" => {
+                self.expect('{')?;
+                RawKind::Choice(self.seq('}', Some(','), |p| {
+                    let id = p.number()?;
+                    let name = p.ident()?;
+                    p.expect('(')?;
+                    let r#type = p.r#type()?;
+                    p.expect(')')?;
+                    Ok((id, name, r#type))
+                })?)
+            }
+            "struct" => {
+                self.expect('{')?;
+                RawKind::Struct(self.seq('}', Some(','), |p| {
+                    let name = p.ident()?;
+                    p.expect(':')?;
+                    let r#type = p.r#type()?;
+                    let default = if p.eat('=') {
+                        p.skip_ws();
+                        let pos = p.pos;
+                        Some((p.default()?, pos))
+                    } else {
+                        None
+                    };
+                    Ok((name, r#type, default))
+                })?)
+            }
+            kind => {
+                return Err(ParseError { kind: ParseErrorKind::UnknownKind(kind.into()), pos: kind_pos });
+            }
+        };
+        Ok(RawDef { name, type_id, generics, kind, pos })
+    }
+}
+
+// the default converted to fit `type`, if it does
+fn field_default(r#type: &Type, default: &FieldDefault) -> Option<FieldDefault> {
+    Some(match (r#type, default) {
+        (_, FieldDefault::Zero) => FieldDefault::Zero,
+        (Type::Uint, FieldDefault::Uint(n)) => FieldDefault::Uint(*n),
+        (Type::Int, FieldDefault::Uint(n)) => FieldDefault::Int(i128::try_from(*n).ok()?),
+        (Type::Int, FieldDefault::Int(n)) => FieldDefault::Int(*n),
+        (Type::Bool, FieldDefault::Bool(b)) => FieldDefault::Bool(*b),
+        (Type::String, FieldDefault::String(chars)) => FieldDefault::String(chars.clone()),
+        _ => return None,
+    })
+}
+
+fn check_unique<T: Ord + Clone>(items: impl Iterator<Item = T>, pos: usize, f: impl Fn(T) -> ParseErrorKind) -> Result<()> {
+    let mut seen = BTreeSet::new();
+    for item in items {
+        if !seen.insert(item.clone()) {
+            return Err(ParseError { kind: f(item), pos });
+        }
+    }
+    Ok(())
+}
+
+struct Resolver<'r, 'a> {
+    defs: &'r [RawDef<'a>],
+    names: &'r BTreeMap<&'a str, usize>,
+    params: &'r [&'a str],
+}
+
+impl<'r, 'a> Resolver<'r, 'a> {
+    fn types(&self, raws: &[RawType<'a>]) -> Result<Box<[Type]>> {
+        raws.iter().map(|raw| self.r#type(raw)).collect()
+    }
+
+    fn r#type(&self, raw: &RawType<'a>) -> Result<Type> {
+        Ok(match raw {
+            RawType::Resolved(r#type) => r#type.clone(),
+            RawType::List(item) => Type::List(Box::new(self.r#type(item)?)),
+            RawType::Option(item) => Type::Option(Box::new(self.r#type(item)?)),
+            RawType::Map(key, value) => Type::Map(Box::new(self.r#type(key)?), Box::new(self.r#type(value)?)),
+            RawType::Tuple(items) => Type::Tuple(self.types(items)?),
+            RawType::Named { name, generics, pos } => {
+                let generics_len_err = |exp_len| ParseError {
+                    kind: ParseErrorKind::GenericsLen { name: (*name).into(), len: generics.len(), exp_len },
+                    pos: *pos,
+                };
+                if let Some(index) = self.params.iter().position(|param| param == name) {
+                    if !generics.is_empty() {
+                        return Err(generics_len_err(0));
+                    }
+                    Type::Param(index as u32)
+                } else if let Some(def) = self.names.get(name).map(|i| &self.defs[*i]) {
+                    if generics.len() != def.generics.len() {
+                        return Err(generics_len_err(def.generics.len()));
+                    }
+                    let generics = self.types(generics)?;
+                    let type_id = def.type_id.clone();
+                    match def.kind {
+                        RawKind::Alias(_) => Type::Alias(type_id, generics),
+                        RawKind::Enum(_) => Type::Enum(type_id),
+                        RawKind::Choice(_) => Type::Choice(type_id, generics),
+                        RawKind::Struct(_) => Type::Struct(type_id, generics),
+                    }
+                } else {
+                    return Err(ParseError { kind: ParseErrorKind::UnknownType((*name).into()), pos: *pos });
+                }
+            }
+        })
+    }
+
+    fn def(&self, raw: &RawDef<'a>) -> Result<Def> {
+        check_unique(raw.generics.iter(), raw.pos, |param| ParseErrorKind::DuplicateParam((*param).into()))?;
+        let kind = match &raw.kind {
+            RawKind::Alias(r#type) => DefKind::Alias(self.r#type(r#type)?),
+            RawKind::Enum(variants) => {
+                check_unique(variants.iter().map(|v| v.id), raw.pos, ParseErrorKind::DuplicateVariantId)?;
+                check_unique(variants.iter().map(|v| &v.name), raw.pos, |name| ParseErrorKind::DuplicateVariantName(name.clone()))?;
+                DefKind::Enum(variants.as_slice().into())
+            }
+            RawKind::Choice(variants) => {
+                check_unique(variants.iter().map(|v| v.0), raw.pos, ParseErrorKind::DuplicateVariantId)?;
+                check_unique(variants.iter().map(|v| v.1), raw.pos, |name| ParseErrorKind::DuplicateVariantName(name.into()))?;
+                DefKind::Choice(variants.iter().map(|(id, name, r#type)| Ok(ChoiceVariant {
+                    id: *id,
+                    name: (*name).into(),
+                    r#type: self.r#type(r#type)?,
+                })).collect::<Result<_>>()?)
+            }
+            RawKind::Struct(fields) => {
+                check_unique(fields.iter().map(|f| f.0), raw.pos, |name| ParseErrorKind::DuplicateField(name.into()))?;
+                DefKind::Struct(fields.iter().map(|(name, r#type, default)| {
+                    let r#type = self.r#type(r#type)?;
+                    let default = match default {
+                        Some((default, pos)) => Some(field_default(&r#type, default).ok_or_else(|| ParseError {
+                            kind: ParseErrorKind::DefaultTypeMismatch { field: (*name).into(), r#type: r#type.clone() },
+                            pos: *pos,
+                        })?),
+                        None => None,
+                    };
+                    Ok(Field { name: (*name).into(), r#type, default })
+                }).collect::<Result<_>>()?)
+            }
+        };
+        Ok(Def {
+            name: raw.name.into(),
+            type_id: raw.type_id.clone(),
+            generics: raw.generics.iter().map(|param| (*param).into()).collect(),
+            kind,
+        })
+    }
+}
+
+pub fn parse(src: &str) -> Result<Registry> {
+    let mut parser = Parser { src, pos: 0 };
+    let mut raw_defs = Vec::new();
+    while parser.peek().is_some() {
+        raw_defs.push(parser.def()?);
+    }
+
+    let mut names = BTreeMap::new();
+    for (i, raw) in raw_defs.iter().enumerate() {
+        if names.insert(raw.name, i).is_some() {
+            return Err(ParseError { kind: ParseErrorKind::DuplicateName(raw.name.into()), pos: raw.pos });
+        }
+    }
+
+    let mut registry = Registry::new();
+    for raw in &raw_defs {
+        let resolver = Resolver { defs: &raw_defs, names: &names, params: &raw.generics };
+        if registry.insert(resolver.def(raw)?).is_some() {
+            return Err(ParseError { kind: ParseErrorKind::DuplicateTypeId(raw.type_id.clone()), pos: raw.pos });
+        }
+    }
+    Ok(registry)
+}
+
+impl Registry {
+    pub fn parse(src: &str) -> Result<Registry> {
+        parse(src)
+    }
+}
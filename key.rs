@@ -0,0 +1,199 @@
+// Key encoding, whose bytes compare as the values do with `canonical_cmp`, for values used
+// as keys of sorted stores. It is not decodable.
+//
+// Every value and type starts with its variant index. Fixed-width integers are big-endian,
+// signed ones with the sign bit flipped. Big integers are a sign byte (signed ones only),
+// the trimmed length as a u64 (inverted if negative) and the trimmed bytes big-endian.
+// Byte strings escape 0x00 as 0x00 0xFF and end with 0x00 0x00. Other sequences put 0x01
+// before each item and end with 0x00.
+
+use super::{*, byte_storage::Output, writer::{Writer, Sink}};
+
+// follows the declaration order, as the derived `Ord` does
+const fn type_variant_index(r#type: &Type) -> u8 {
+    match r#type {
+        Type::Unknown => 0,
+        Type::Uint => 1,
+        Type::Int => 2,
+        Type::Bool => 3,
+        Type::BigUint => 4,
+        Type::BigInt => 5,
+        Type::Uints => 6,
+        Type::Bytes => 7,
+        Type::String => 8,
+        Type::Utf8 => 9,
+        Type::Tuple(_) => 10,
+        Type::List(_) => 11,
+        Type::Option(_) => 12,
+        Type::Map(..) => 13,
+        Type::Alias(..) => 14,
+        Type::Enum(_) => 15,
+        Type::Choice(..) => 16,
+        Type::Struct(..) => 17,
+        Type::Type => 18,
+        Type::TypeId => 19,
+        Type::Param(_) => 20,
+    }
+}
+
+impl<S: Sink> Writer<S> {
+    fn k_be(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.byte(*byte);
+        }
+    }
+
+    fn k_uint(&mut self, n: u128) {
+        self.k_be(&n.to_be_bytes());
+    }
+
+    fn k_int(&mut self, n: i128) {
+        self.k_uint((n as u128) ^ (1 << 127));
+    }
+
+    // of the same length, two's complements of the same sign compare as their bytes big-endian
+    fn k_big(&mut self, le: &[u8], negative: bool) {
+        let len = le.len() as u64;
+        let len = if negative { !len } else { len };
+        self.k_be(&len.to_be_bytes());
+        for byte in le.iter().rev() {
+            self.byte(*byte);
+        }
+    }
+
+    fn k_bytes(&mut self, bytes: &[u8]) {
+        for byte in bytes {
+            self.byte(*byte);
+            if *byte == 0 {
+                self.byte(0xFF);
+            }
+        }
+        self.k_be(&[0, 0]);
+    }
+
+    fn k_seq<T>(&mut self, items: &[T], mut f: impl FnMut(&mut Self, &T)) {
+        for item in items {
+            self.byte(1);
+            f(self, item);
+        }
+        self.byte(0);
+    }
+
+    fn k_type_id(&mut self, type_id: &TypeId) {
+        match type_id {
+            TypeId::Anonymous => self.byte(0),
+            TypeId::Std(id) => {
+                self.byte(1);
+                self.k_uint(*id);
+            }
+            TypeId::ThirdParty(namespace, id) => {
+                self.byte(2);
+                self.k_uint(*namespace);
+                self.k_uint(*id);
+            }
+        }
+    }
+
+    fn k_types(&mut self, types: &[Type]) {
+        self.k_seq(types, Self::k_type);
+    }
+
+    fn k_type(&mut self, r#type: &Type) {
+        self.byte(type_variant_index(r#type));
+        match r#type {
+            Type::Tuple(types) => self.k_types(types),
+            Type::List(type2) | Type::Option(type2) => self.k_type(type2),
+            Type::Map(key_type, value_type) => {
+                self.k_type(key_type);
+                self.k_type(value_type);
+            }
+            Type::Alias(type_id, generics)
+            | Type::Choice(type_id, generics)
+            | Type::Struct(type_id, generics) => {
+                self.k_type_id(type_id);
+                self.k_types(generics);
+            }
+            Type::Enum(type_id) => self.k_type_id(type_id),
+            Type::Param(index) => self.k_be(&index.to_be_bytes()),
+            _ => {}
+        }
+    }
+
+    fn k_values<B: AsRef<[u8]> + ByteStorage>(&mut self, values: &[Value<B>]) {
+        self.k_seq(values, Self::k_value);
+    }
+
+    fn k_value<B: AsRef<[u8]> + ByteStorage>(&mut self, value: &Value<B>) {
+        self.byte(ord::variant_index(value));
+        match value {
+            Value::Uint(n) => self.k_uint(*n),
+            Value::Int(n) => self.k_int(*n),
+            Value::Bool(n) => self.byte(*n as u8),
+            Value::BigUint(le) => self.k_big(bigint::trim_uint(le), false),
+            Value::BigInt(le) => {
+                let negative = bigint::is_negative(le);
+                self.byte(!negative as u8);
+                self.k_big(bigint::trim_int(le), negative);
+            }
+            Value::Uints(uints) => self.k_seq(uints, |writer, n| writer.k_uint(*n)),
+            Value::Bytes(bytes) | Value::Utf8(bytes) => self.k_bytes(bytes.as_ref()),
+            Value::String(chars) => self.k_seq(chars, |writer, char| writer.k_be(&(*char as u32).to_be_bytes())),
+            Value::Tuple(values) => self.k_values(values),
+            Value::List(r#type, values) => {
+                self.k_type(r#type);
+                self.k_values(values);
+            }
+            Value::Option(r#type, value) => {
+                self.k_type(r#type);
+                match value {
+                    Some(value) => {
+                        self.byte(1);
+                        self.k_value(value);
+                    }
+                    None => self.byte(0),
+                }
+            }
+            // entries as they are, which is the canonical order for maps read or built by `Value::map`
+            Value::Map(key_type, value_type, entries) => {
+                self.k_type(key_type);
+                self.k_type(value_type);
+                self.k_seq(entries, |writer, (key, value)| {
+                    writer.k_value(key);
+                    writer.k_value(value);
+                });
+            }
+            Value::Alias(type_id, generics, value) => {
+                self.k_type_id(type_id);
+                self.k_types(generics);
+                self.k_value(value);
+            }
+            Value::Enum(type_id, variant_id) => {
+                self.k_type_id(type_id);
+                self.k_uint(*variant_id);
+            }
+            Value::Choice(type_id, generics, variant_id, value) => {
+                self.k_type_id(type_id);
+                self.k_types(generics);
+                self.k_uint(*variant_id);
+                self.k_value(value);
+            }
+            Value::Struct(type_id, generics, values) => {
+                self.k_type_id(type_id);
+                self.k_types(generics);
+                self.k_values(values);
+            }
+            Value::Type(r#type) => self.k_type(r#type),
+            Value::TypeId(type_id) => self.k_type_id(type_id),
+        }
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    /// Encodes the value so that comparing the bytes of two keys orders them as `canonical_cmp`
+    /// orders the values. Only for comparing, there is no decoding.
+    pub fn encode_key<O: Output>(&self) -> O::Storage {
+        let mut writer = Writer::<O>::new();
+        writer.k_value(self);
+        writer.into_inner()
+    }
+}
@@ -168,9 +168,10 @@ impl<T: NumUnsigned> Default for DecodeState<T> {
 
 // region: reader
 
+use alloc::{boxed::Box, vec, vec::Vec};
 use crate::{byte_storage::{ByteStorage, Input}, reader::Reader};
 
-impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
+impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<'_, I> {
     fn uleb128_inner<T: NumUnsigned>(
         &mut self,
         state: Option<DecodeState<T>>,
@@ -316,6 +317,62 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
         Ok(res)
     }
 
+    // 7-bit groups of an unbounded LEB128 of at most `max_len` bytes
+    fn leb128_groups(&mut self, max_len: usize) -> crate::Result<Vec<u8>> {
+        let mut groups = Vec::new();
+        loop {
+            let byte = self.byte()?;
+            if groups.len() == max_len {
+                return Err(crate::Error::MaxLen(crate::MaxLenType::BigInt, crate::MaxLenExceedValue::Size(max_len + 1)));
+            }
+            groups.push(byte & 0x7F);
+            if byte & 0x80 == 0 {
+                break;
+            }
+        }
+        Ok(groups)
+    }
+
+    // little-endian bytes of `groups`, the bits above them set if `negative`
+    fn groups_to_le(groups: &[u8], negative: bool) -> Vec<u8> {
+        let mut le = vec![0u8; (groups.len() * 7).div_ceil(8)];
+        for (i, group) in groups.iter().enumerate() {
+            let (index, shift) = (i * 7 / 8, i * 7 % 8);
+            le[index] |= group << shift;
+            if shift > 1 {
+                le[index + 1] |= group >> (8 - shift);
+            }
+        }
+        let extra = le.len() * 8 - groups.len() * 7;
+        if negative && extra > 0 && let Some(last) = le.last_mut() {
+            *last |= 0xFF << (8 - extra);
+        }
+        le
+    }
+
+    pub(crate) fn uleb128_big(&mut self, max_len: usize) -> crate::Result<Box<[u8]>> {
+        let groups = self.leb128_groups(max_len)?;
+        if let [.., _, 0] = *groups {
+            return Err(crate::Error::LEB128TrailingEmptyBytes);
+        }
+        let le = Self::groups_to_le(&groups, false);
+        Ok(crate::bigint::trim_uint(&le).into())
+    }
+
+    pub(crate) fn sleb128_big(&mut self, max_len: usize) -> crate::Result<Box<[u8]>> {
+        let groups = self.leb128_groups(max_len)?;
+        if let [.., last_byte, byte] = *groups {
+            let pos = byte == 0 && last_byte & 0x40 == 0;
+            let neg = byte == 0x7F && last_byte & 0x40 != 0;
+            if pos || neg {
+                return Err(crate::Error::LEB128TrailingEmptyBytes);
+            }
+        }
+        let negative = groups.last().is_some_and(|group| group & 0x40 != 0);
+        let le = Self::groups_to_le(&groups, negative);
+        Ok(crate::bigint::trim_int(&le).into())
+    }
+
     pub(crate) fn sleb128<N: NumSigned>(&mut self) -> crate::Result<N> {
         let res = self.sleb128_inner::<N>(None);
         match res {
@@ -338,9 +395,9 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
 
 // region: writer
 
-use crate::{byte_storage::Output, writer::Writer};
+use crate::writer::{Writer, Sink};
 
-impl<O: Output> Writer<O> {
+impl<S: Sink> Writer<S> {
     pub(crate) fn uleb128(&mut self, mut n: impl NumUnsigned) {
         loop {
             let byte = n.trunc_u8() & 0x7F;
@@ -355,6 +412,28 @@ impl<O: Output> Writer<O> {
         }
     }
 
+    // `len` 7-bit groups of `le`, extended with `pad`
+    fn leb128_groups(&mut self, le: &[u8], pad: u8, len: usize) {
+        let byte = |index: usize| le.get(index).copied().unwrap_or(pad) as u16;
+        for i in 0..len {
+            let (index, shift) = (i * 7 / 8, i * 7 % 8);
+            let group = ((byte(index) | byte(index + 1) << 8) >> shift) as u8 & 0x7F;
+            self.byte(if i + 1 < len { group | 0x80 } else { group });
+        }
+    }
+
+    pub(crate) fn uleb128_big(&mut self, le: &[u8]) {
+        let le = crate::bigint::trim_uint(le);
+        self.leb128_groups(le, 0, crate::bigint::bit_len(le, false).div_ceil(7).max(1));
+    }
+
+    pub(crate) fn sleb128_big(&mut self, le: &[u8]) {
+        let le = crate::bigint::trim_int(le);
+        let negative = crate::bigint::is_negative(le);
+        let pad = if negative { 0xFF } else { 0 };
+        self.leb128_groups(le, pad, (crate::bigint::bit_len(le, negative) + 1).div_ceil(7));
+    }
+
     pub(crate) fn sleb128(&mut self, n: impl NumSigned) {
         let mut n = n.as_unsigned();
         loop {
@@ -11,6 +11,7 @@ use alloc::boxed::Box;
 use foundations::{error_enum, num_enum_reverse};
 pub use byte_storage;
 use byte_storage::{ByteStorage, ReadError};
+use check::CheckError;
 
 type VariantId = u128;
 
@@ -19,15 +20,19 @@ num_enum_reverse! {
         b'U' = Uint,
         b'I' = Int,
         b'F' = Bool,
+        b'V' = BigUint,
+        b'W' = BigInt,
 
         b'N' = Uints,
         b'B' = Bytes,
         b'S' = String,
+        b'Q' = Utf8,
 
         b'P' = Tuple,
         b'L' = List,
         // TODO: O&0 distinguish?
         b'O' = Option,
+        b'K' = Map,
 
         b'A' = Alias,
         b'E' = Enum,
@@ -40,6 +45,17 @@ num_enum_reverse! {
         // implicit tuple types
         b'M' = ListItems,
         b'G' = Generics,
+
+        // alternative encodings of `Value::Uints`, chosen by the writer
+        b'H' = UintsDelta,
+        b'J' = UintsPacked,
+
+        // document-level type table, and references into it in place of `T(..)` and `D(..)`
+        b'X' = TypeTable,
+        b'Y' = TypeRef,
+
+        // `Z(F1 v)` shares a value, `Z(F0 U<index>)` refers back to a shared one
+        b'Z' = Shared,
     } as u8 else Error::Tag
 }
 
@@ -52,15 +68,19 @@ num_enum_reverse! {
         b'u' = Uint,
         b'i' = Int,
         b'f' = Bool,
+        b'v' = BigUint,
+        b'w' = BigInt,
 
         b'n' = Uints,
         b'b' = Bytes,
         b's' = String,
+        b'q' = Utf8,
 
         b'p' = Tuple,
         // TODO: I&l distinguish?
         b'l' = List,
         b'o' = Option,
+        b'k' = Map,
 
         b'a' = Alias,
         b'e' = Enum,
@@ -69,6 +89,8 @@ num_enum_reverse! {
 
         b't' = Type,
         b'd' = TypeId,
+
+        b'g' = Param,
     } as u8 else Error::TypeTag
 }
 
@@ -76,7 +98,7 @@ num_enum_reverse! {
     pub enum TypeIdTag {
         b'x' = Anonymous,
         b'y' = Std,
-        // b'z' = ThirdParty,
+        b'z' = ThirdParty,
     } as u8 else Error::TypeIdTag
 }
 
@@ -88,14 +110,19 @@ pub enum Type {
     Uint,
     Int,
     Bool,
+    BigUint,
+    BigInt,
 
     Uints,
     Bytes,
     String,
+    Utf8,
 
     Tuple(Box<[Type]>),
     List(Box<Type>),
     Option(Box<Type>),
+    // key type, value type
+    Map(Box<Type>, Box<Type>),
 
     Alias(TypeId, Box<[Type]>),
     Enum(TypeId),
@@ -104,6 +131,9 @@ pub enum Type {
 
     Type,
     TypeId,
+
+    // index into the generics of the enclosing definition
+    Param(u32),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -111,7 +141,8 @@ pub enum Type {
 pub enum TypeId {
     Anonymous,
     Std(u128),
-    // TODO third-party
+    // namespace id, id in namespace
+    ThirdParty(u128, u128),
 }
 
 // TODO impl<B1: PartialEq<B2>, B2> PartialEq&PartialOrd<Value<B2>> for Value<B1>
@@ -123,14 +154,22 @@ pub enum Value<B: AsRef<[u8]> + ByteStorage> {
     Uint(u128),
     Int(i128),
     Bool(bool),
+    // little-endian magnitude without trailing zero bytes, zero is empty
+    BigUint(Box<[u8]>),
+    // little-endian two's complement in the fewest bytes, zero is empty
+    BigInt(Box<[u8]>),
 
     Uints(Box<[u128]>),
     Bytes(B),
     String(Box<[char]>),
+    // valid UTF-8, as validated by the reader and `Value::utf8`
+    Utf8(B),
 
     Tuple(Box<[Value<B>]>),
     List(Type, Box<[Value<B>]>),
     Option(Type, Option<Box<Value<B>>>),
+    // key type, value type, entries in canonical key order, see `Value::canonical_cmp`
+    Map(Type, Type, Box<[(Value<B>, Value<B>)]>),
 
     Alias(TypeId, Box<[Type]>, Box<Value<B>>),
     Enum(TypeId, VariantId),
@@ -143,33 +182,50 @@ pub enum Value<B: AsRef<[u8]> + ByteStorage> {
 
 pub struct MaxLens {
     pub uints: usize,
+    // encoded bytes of a big integer
+    pub big_int: usize,
     pub bytes: usize,
     pub string: usize,
+    pub utf8: usize,
     pub tuple: usize,
     pub list: usize,
+    pub map: usize,
     pub generics: usize,
     pub variants: u128,
+    pub type_table: usize,
+    // values and sequence items copied by references to shared values
+    pub expansion: usize,
 }
 
 pub const DEFAULT_MAX_LENS: MaxLens = MaxLens {
     uints: u32::MAX as usize,
+    big_int: u32::MAX as usize,
     bytes: u32::MAX as usize,
     string: u32::MAX as usize,
+    utf8: u32::MAX as usize,
     tuple: u32::MAX as usize,
     list: u32::MAX as usize,
+    map: u32::MAX as usize,
     generics: u32::MAX as usize,
     variants: u32::MAX as u128,
+    type_table: u32::MAX as usize,
+    expansion: u32::MAX as usize,
 };
 
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub enum MaxLenType {
     Uints,
+    BigInt,
     Bytes,
     String,
+    Utf8,
     Tuple,
     List,
+    Map,
     Generics,
     Variants,
+    TypeTable,
+    Expansion,
 }
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -198,13 +254,33 @@ error_enum! {
         LEB128TrailingEmptyBytes,
         MaxLen(MaxLenType, MaxLenExceedValue),
         U32ToChar(u32),
+        UintsDeltaOverflow,
+        UintsBitWidth(u8),
+        UintsPackedPadding,
+        Utf8(core::str::Utf8Error),
         FixedTupleLen { len: u8, exp_len: u8 },
         // TODO distinguish inner type & user type
         ExpectedTypeMismatch { tag: Tag, exp_tag: Tag },
         EmptyListInNotEmptyMark,
+        ListItemTypeMismatch { r#type: Type, item_type: Type },
+        MapEntryTypeMismatch { r#type: Type, entry_type: Type },
+        DuplicateMapKey,
+        UnorderedMapKeys,
         ImplicitTypeOnTop(Tag),
+        TypeTableNotOnTop,
+        UnknownTypeRef(usize),
+        UnknownSharedRef(usize),
+        // shared values outside `decode_shared`
+        UnexpectedShared,
+        MissingFieldDefault { type_id: TypeId, index: usize },
+        UnknownFields { type_id: TypeId, len: usize, exp_len: usize },
+        ParamInSchema(u32),
+        // index built from another list
+        ListIndexMismatch,
+        NonCanonical,
     } convert {
         Read => ReadError,
+        Check => CheckError,
     }
 }
 
@@ -216,9 +292,32 @@ pub mod casting;
 
 pub mod reader;
 pub mod writer;
+mod key;
+pub mod value_ref;
+pub mod query;
+pub use query::query;
+pub mod digest;
 
 #[cfg(feature = "text-writer")]
 pub mod text_writer;
 
+mod typing;
+mod ord;
+mod bigint;
+
+pub mod schema;
+pub mod idl;
+pub mod check;
+mod compact;
+pub mod compat;
+pub mod tolerant;
+pub mod migrate;
+mod defaults;
+pub mod convert;
+pub mod well_known;
+
+#[cfg(feature = "codegen")]
+pub mod codegen;
+
 #[cfg(test)]
 mod tests;
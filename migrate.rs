@@ -0,0 +1,113 @@
+// Upgrading decoded values written with older versions of their definitions.
+// Versions are not on the wire, the caller knows which version the data was written with.
+
+use alloc::{vec::Vec, collections::BTreeMap};
+use super::{*, convert::ConvertError};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MigrationError {
+    Convert(ConvertError),
+    Custom(Box<str>),
+}
+
+impl From<ConvertError> for MigrationError {
+    fn from(err: ConvertError) -> Self {
+        MigrationError::Convert(err)
+    }
+}
+
+type Result<T> = core::result::Result<T, MigrationError>;
+
+pub type MigrationFn<B> = dyn Fn(Value<B>) -> Result<Value<B>>;
+
+fn defined_type_id<B: AsRef<[u8]> + ByteStorage>(value: &Value<B>) -> Option<&TypeId> {
+    match value {
+        Value::Alias(type_id, ..)
+        | Value::Choice(type_id, ..)
+        | Value::Struct(type_id, ..) => Some(type_id),
+        _ => None,
+    }
+}
+
+pub struct Migrations<B: AsRef<[u8]> + ByteStorage> {
+    // (type id, version migrated from) -> migration to the next version
+    fns: BTreeMap<(TypeId, u32), Box<MigrationFn<B>>>,
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Default for Migrations<B> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Migrations<B> {
+    pub fn new() -> Self {
+        Self { fns: BTreeMap::new() }
+    }
+
+    /// Registers `f` upgrading values of `type_id` from `from_version` to `from_version + 1`.
+    /// Returns whether a migration was already registered for the pair, which is replaced.
+    pub fn insert(&mut self, type_id: TypeId, from_version: u32, f: impl Fn(Value<B>) -> Result<Value<B>> + 'static) -> bool {
+        self.fns.insert((type_id, from_version), Box::new(f)).is_some()
+    }
+
+    pub fn get(&self, type_id: &TypeId, from_version: u32) -> Option<&MigrationFn<B>> {
+        self.fns.get(&(type_id.clone(), from_version)).map(|f| &**f)
+    }
+
+    pub fn len(&self) -> usize {
+        self.fns.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fns.is_empty()
+    }
+
+    fn migrate_seq(&self, values: Box<[Value<B>]>, version: u32) -> Result<Box<[Value<B>]>> {
+        values.into_vec().into_iter().map(|value| self.migrate(value, version)).collect()
+    }
+
+    fn migrate_box(&self, value: Box<Value<B>>, version: u32) -> Result<Box<Value<B>>> {
+        Ok(Box::new(self.migrate(*value, version)?))
+    }
+
+    /// Upgrades every `Alias`, `Choice` and `Struct` in `value` written at `version`, children first.
+    /// Migrations of a type id are chained from `version` on until one is missing, each one seeing
+    /// the type id the previous one returned. Types in list and option headers are left as they are.
+    pub fn migrate(&self, value: Value<B>, version: u32) -> Result<Value<B>> {
+        let mut value = match value {
+            Value::Tuple(values) => Value::Tuple(self.migrate_seq(values, version)?),
+            Value::List(r#type, values) => Value::List(r#type, self.migrate_seq(values, version)?),
+            Value::Option(r#type, value) => {
+                Value::Option(r#type, value.map(|value| self.migrate_box(value, version)).transpose()?)
+            }
+            Value::Map(key_type, value_type, entries) => {
+                let entries = entries.into_vec().into_iter()
+                    .map(|(key, value)| Ok((self.migrate(key, version)?, self.migrate(value, version)?)))
+                    .collect::<Result<Vec<_>>>()?;
+                // keys may have been migrated out of order
+                Value::map(key_type, value_type, entries)
+            }
+            Value::Alias(type_id, generics, value) => Value::Alias(type_id, generics, self.migrate_box(value, version)?),
+            Value::Choice(type_id, generics, variant_id, value) => {
+                Value::Choice(type_id, generics, variant_id, self.migrate_box(value, version)?)
+            }
+            Value::Struct(type_id, generics, values) => Value::Struct(type_id, generics, self.migrate_seq(values, version)?),
+            value => return Ok(value),
+        };
+        let mut version = version;
+        while let Some(f) = defined_type_id(&value).and_then(|type_id| self.get(type_id, version)) {
+            value = f(value)?;
+            version += 1;
+        }
+        Ok(value)
+    }
+}
+
+/// Field values of a struct value, for migrations rebuilding it.
+pub fn struct_values<B: AsRef<[u8]> + ByteStorage>(value: Value<B>) -> Result<(TypeId, Box<[Type]>, Vec<Value<B>>)> {
+    match value {
+        Value::Struct(type_id, generics, values) => Ok((type_id, generics, values.into_vec())),
+        value => Err(ConvertError::Tag { tag: value.as_tag(), exp_tag: Tag::Struct }.into()),
+    }
+}
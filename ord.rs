@@ -0,0 +1,148 @@
+// Ordering values without requiring `B: Ord`. It is the derived `Ord` whenever `B` orders as its bytes,
+// except that big integers are ordered numerically, and decides the canonical order of map keys.
+
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+use super::*;
+
+pub(crate) const fn variant_index<B: AsRef<[u8]> + ByteStorage>(value: &Value<B>) -> u8 {
+    match value {
+        Value::Uint(_) => 0,
+        Value::Int(_) => 1,
+        Value::Bool(_) => 2,
+        Value::BigUint(_) => 3,
+        Value::BigInt(_) => 4,
+        Value::Uints(_) => 5,
+        Value::Bytes(_) => 6,
+        Value::String(_) => 7,
+        Value::Utf8(_) => 8,
+        Value::Tuple(_) => 9,
+        Value::List(..) => 10,
+        Value::Option(..) => 11,
+        Value::Map(..) => 12,
+        Value::Alias(..) => 13,
+        Value::Enum(..) => 14,
+        Value::Choice(..) => 15,
+        Value::Struct(..) => 16,
+        Value::Type(_) => 17,
+        Value::TypeId(_) => 18,
+    }
+}
+
+fn cmp_seq<T>(a: &[T], b: &[T], cmp: impl Fn(&T, &T) -> Ordering) -> Ordering {
+    a.iter().zip(b.iter())
+        .map(|(a, b)| cmp(a, b))
+        .find(|ord| ord.is_ne())
+        .unwrap_or_else(|| a.len().cmp(&b.len()))
+}
+
+fn cmp_values<B: AsRef<[u8]> + ByteStorage>(a: &[Value<B>], b: &[Value<B>], read: bool) -> Ordering {
+    cmp_seq(a, b, |a, b| a.cmp_as(b, read))
+}
+
+fn cmp_entries<B: AsRef<[u8]> + ByteStorage>(a: &[(Value<B>, Value<B>)], b: &[(Value<B>, Value<B>)], read: bool) -> Ordering {
+    cmp_seq(a, b, |(a_key, a_value), (b_key, b_value)| a_key.cmp_as(b_key, read).then_with(|| a_value.cmp_as(b_value, read)))
+}
+
+// the unified types of `types`, the first one if they do not unify as the lenient reader does
+fn unify_all(mut types: impl Iterator<Item = Type>) -> Option<Type> {
+    let first = types.next()?;
+    Some(types.try_fold(first.clone(), |r#type, item_type| Type::unify(&r#type, &item_type)).unwrap_or(first))
+}
+
+// the type of the value as the reader gives it, where the types of lists, options and maps with
+// items are not the ones they carry but the ones the reader unifies from the items
+pub(crate) fn read_type<B: AsRef<[u8]> + ByteStorage>(value: &Value<B>) -> Type {
+    match value {
+        Value::Tuple(values) => Type::Tuple(values.iter().map(read_type).collect()),
+        Value::List(r#type, values) => Type::List(Box::new(unify_all(values.iter().map(read_type)).unwrap_or_else(|| r#type.clone()))),
+        Value::Option(_, Some(value)) => Type::Option(Box::new(read_type(value))),
+        Value::Map(key_type, value_type, entries) => Type::Map(
+            Box::new(unify_all(entries.iter().map(|(key, _)| read_type(key))).unwrap_or_else(|| key_type.clone())),
+            Box::new(unify_all(entries.iter().map(|(_, value)| read_type(value))).unwrap_or_else(|| value_type.clone())),
+        ),
+        value => value.as_type(),
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    pub fn canonical_cmp(&self, other: &Value<B>) -> Ordering {
+        self.cmp_as(other, false)
+    }
+
+    // `canonical_cmp` of the values as the reader gives them, see `read_type`, which is the order
+    // of map keys on the wire
+    pub(crate) fn read_cmp(&self, other: &Value<B>) -> Ordering {
+        self.cmp_as(other, true)
+    }
+
+    fn cmp_as(&self, other: &Value<B>, read: bool) -> Ordering {
+        // the types lists, options and maps carry
+        let cmp_types = |a_types: &[&Type], b_types: &[&Type]| if read {
+            read_type(self).cmp(&read_type(other))
+        } else {
+            a_types.cmp(b_types)
+        };
+        match (self, other) {
+            (Value::Uint(a), Value::Uint(b)) => a.cmp(b),
+            (Value::Int(a), Value::Int(b)) => a.cmp(b),
+            (Value::Bool(a), Value::Bool(b)) => a.cmp(b),
+            (Value::BigUint(a), Value::BigUint(b)) => bigint::cmp_uint(a, b),
+            (Value::BigInt(a), Value::BigInt(b)) => bigint::cmp_int(a, b),
+            (Value::Uints(a), Value::Uints(b)) => a.cmp(b),
+            (Value::Bytes(a), Value::Bytes(b)) => a.as_ref().cmp(b.as_ref()),
+            (Value::String(a), Value::String(b)) => a.cmp(b),
+            (Value::Utf8(a), Value::Utf8(b)) => a.as_ref().cmp(b.as_ref()),
+            (Value::Tuple(a), Value::Tuple(b)) => cmp_values(a, b, read),
+            (Value::List(a_type, a), Value::List(b_type, b)) => cmp_types(&[a_type], &[b_type])
+                .then_with(|| cmp_values(a, b, read)),
+            (Value::Option(a_type, a), Value::Option(b_type, b)) => cmp_types(&[a_type], &[b_type])
+                .then_with(|| match (a, b) {
+                    (Some(a), Some(b)) => a.cmp_as(b, read),
+                    (a, b) => a.is_some().cmp(&b.is_some()),
+                }),
+            (Value::Map(a_key_type, a_value_type, a), Value::Map(b_key_type, b_value_type, b)) => {
+                cmp_types(&[a_key_type, a_value_type], &[b_key_type, b_value_type]).then_with(|| cmp_entries(a, b, read))
+            }
+            (Value::Alias(a_type_id, a_generics, a), Value::Alias(b_type_id, b_generics, b)) => a_type_id.cmp(b_type_id)
+                .then_with(|| a_generics.cmp(b_generics))
+                .then_with(|| a.cmp_as(b, read)),
+            (Value::Enum(a_type_id, a), Value::Enum(b_type_id, b)) => a_type_id.cmp(b_type_id).then_with(|| a.cmp(b)),
+            (Value::Choice(a_type_id, a_generics, a_id, a), Value::Choice(b_type_id, b_generics, b_id, b)) => a_type_id.cmp(b_type_id)
+                .then_with(|| a_generics.cmp(b_generics))
+                .then_with(|| a_id.cmp(b_id))
+                .then_with(|| a.cmp_as(b, read)),
+            (Value::Struct(a_type_id, a_generics, a), Value::Struct(b_type_id, b_generics, b)) => a_type_id.cmp(b_type_id)
+                .then_with(|| a_generics.cmp(b_generics))
+                .then_with(|| cmp_values(a, b, read)),
+            (Value::Type(a), Value::Type(b)) => a.cmp(b),
+            (Value::TypeId(a), Value::TypeId(b)) => a.cmp(b),
+            (a, b) => variant_index(a).cmp(&variant_index(b)),
+        }
+    }
+
+    /// A map value from `entries` in any order. Of entries with keys read back as equal, such as
+    /// lists carrying different item types for the same items, the last one is kept.
+    pub fn map(key_type: Type, value_type: Type, entries: impl IntoIterator<Item = (Value<B>, Value<B>)>) -> Value<B> {
+        let mut entries: Vec<_> = entries.into_iter().collect();
+        entries.sort_by(|(a, _), (b, _)| a.read_cmp(b));
+        let mut deduped: Vec<(Value<B>, Value<B>)> = Vec::with_capacity(entries.len());
+        for entry in entries {
+            match deduped.last_mut() {
+                Some(last) if last.0.read_cmp(&entry.0).is_eq() => *last = entry,
+                _ => deduped.push(entry),
+            }
+        }
+        Value::Map(key_type, value_type, deduped.into_boxed_slice())
+    }
+}
+
+// entries in key order by `cmp`
+pub(crate) fn sorted_entries<B: AsRef<[u8]> + ByteStorage>(
+    entries: &[(Value<B>, Value<B>)],
+    cmp: fn(&Value<B>, &Value<B>) -> Ordering,
+) -> Vec<&(Value<B>, Value<B>)> {
+    let mut sorted: Vec<_> = entries.iter().collect();
+    sorted.sort_by(|(a, _), (b, _)| cmp(a, b));
+    sorted
+}
@@ -0,0 +1,141 @@
+// Extracting parts of encoded values by paths like `/3/items/*/0`, skipping over
+// everything not on the way without decoding it.
+
+use alloc::vec::Vec;
+use core::ops::Range;
+use super::{*, schema::Registry, value_ref::{ValueRef, Items}};
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum QueryError {
+    Decode(Error),
+    /// Paths start with `/` and have no empty segments.
+    Path(Box<str>),
+    /// Field names can only be resolved with a registry.
+    FieldName(Box<str>),
+}
+
+impl From<Error> for QueryError {
+    fn from(err: Error) -> Self {
+        QueryError::Decode(err)
+    }
+}
+
+type Result<T> = core::result::Result<T, QueryError>;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Segment {
+    /// Item of a tuple or list, field of a struct, content of an option (`0`),
+    /// or payload of a choice with this variant id.
+    Index(usize),
+    /// Field of a struct by its name in the registry.
+    Field(Box<str>),
+    /// Every item, field or map value, the content of an option or the payload of a choice.
+    All,
+}
+
+/// Aliases are looked through, segments that do not apply to a value select nothing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Path(Box<[Segment]>);
+
+impl Path {
+    pub fn parse(path: &str) -> Result<Path> {
+        let invalid = || QueryError::Path(path.into());
+        let rest = path.strip_prefix('/').ok_or_else(invalid)?;
+        if rest.is_empty() {
+            return Ok(Path(Box::new([])));
+        }
+        rest.split('/').map(|segment| Ok(match segment {
+            "" => return Err(invalid()),
+            "*" => Segment::All,
+            segment if segment.bytes().all(|byte| byte.is_ascii_digit()) => {
+                Segment::Index(segment.parse().map_err(|_| invalid())?)
+            }
+            segment => Segment::Field(segment.into()),
+        })).collect::<Result<_>>().map(Path)
+    }
+
+    pub fn segments(&self) -> &[Segment] {
+        &self.0
+    }
+
+    pub fn query<'a>(&self, value: ValueRef<'a>, registry: Option<&Registry>) -> Result<Vec<ValueRef<'a>>> {
+        let mut values = Vec::from([value]);
+        for segment in self.0.iter() {
+            let mut next = Vec::new();
+            for value in values {
+                step(value, segment, registry, &mut next)?;
+            }
+            values = next;
+        }
+        Ok(values)
+    }
+}
+
+fn select<'a>(mut items: Items<'a>, segment: &Segment, out: &mut Vec<ValueRef<'a>>) {
+    match segment {
+        Segment::Index(index) => out.extend(items.nth(*index)),
+        Segment::All => out.extend(items),
+        Segment::Field(_) => {}
+    }
+}
+
+fn step<'a>(value: ValueRef<'a>, segment: &Segment, registry: Option<&Registry>, out: &mut Vec<ValueRef<'a>>) -> Result<()> {
+    match value.tag() {
+        Tag::Alias => return step(value.as_alias()?.1, segment, registry, out),
+        Tag::Tuple => select(value.as_tuple()?, segment, out),
+        Tag::List => select(value.list_iter()?, segment, out),
+        Tag::Struct => {
+            let (type_id, mut fields) = value.as_struct()?;
+            match segment {
+                Segment::Field(name) => {
+                    let registry = registry.ok_or_else(|| QueryError::FieldName(name.clone()))?;
+                    if let Some((index, _)) = registry.get(&type_id).and_then(|def| def.field(name)) {
+                        out.extend(fields.nth(index));
+                    }
+                }
+                segment => select(fields, segment, out),
+            }
+        }
+        Tag::Option => {
+            if let Segment::Index(0) | Segment::All = segment {
+                out.extend(value.as_option()?);
+            }
+        }
+        Tag::Choice => {
+            let (_, variant_id, payload) = value.as_choice()?;
+            match segment {
+                Segment::Index(index) if *index as VariantId == variant_id => out.push(payload),
+                Segment::All => out.push(payload),
+                _ => {}
+            }
+        }
+        Tag::Map => {
+            if let Segment::All = segment {
+                out.extend(value.map_iter()?.map(|(_, value)| value));
+            }
+        }
+        _ => {}
+    }
+    Ok(())
+}
+
+/// A value selected by a query, and where it is in the queried bytes.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Match<'a> {
+    pub range: Range<usize>,
+    pub value: ValueRef<'a>,
+}
+
+pub fn query_with_registry<'a>(buf: &'a [u8], path: &str, registry: Option<&Registry>) -> Result<Vec<Match<'a>>> {
+    let path = Path::parse(path)?;
+    let value = ValueRef::new(buf).map_err(|err| err.err)?;
+    Ok(path.query(value, registry)?.into_iter().map(|value| {
+        let start = value.offset_in(buf);
+        Match { range: start..start + value.encoded().len(), value }
+    }).collect())
+}
+
+/// The parts of the value in `buf` at `path`, see `Path`. Field names need `query_with_registry`.
+pub fn query<'a>(buf: &'a [u8], path: &str) -> Result<Vec<Match<'a>>> {
+    query_with_registry(buf, path, None)
+}
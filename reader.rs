@@ -1,32 +1,77 @@
 #![allow(dead_code)]
 
-use super::{*, byte_storage::Input, leb128::*};
+use alloc::vec::Vec;
+use super::{*, byte_storage::Input, leb128::*, tolerant::Tolerance};
 
 // TODO limit recursive levels?
 
 // We can't avoid allocs completely because of nested values and indefinite-length sequences.
 // So we should check for allocation at sequence creates to ensure no panic.
 #[inline]
-fn alloc_seq<T, F: FnMut(()) -> Result<T>>(len: usize, f: F) -> Result<Box<[T]>> {
+pub(crate) fn alloc_seq<T, F: FnMut(()) -> Result<T>>(len: usize, f: F) -> Result<Box<[T]>> {
     core::iter::repeat_n((), len).map(f).collect()
 }
 
-enum OptionWithType<T> {
-    None(Type),
+pub(crate) enum OptionWithType<T, N = Type> {
+    None(N),
     Some(T),
 }
 
-pub(crate) struct Reader<I> {
+enum TypeTableEntry {
+    Type(Type),
+    TypeId(TypeId),
+}
+
+pub(crate) struct Reader<'r, I: Input<Storage: AsRef<[u8]> + ByteStorage>> {
     inner: byte_storage::Reader<I>,
     max_lens: MaxLens,
+    tolerance: Option<&'r Tolerance<'r>>,
+    type_table: Vec<TypeTableEntry>,
+    // shared values and their expanded lengths
+    shared: Vec<(Value<I::Storage>, usize)>,
+    expanded: usize,
+    // copies shared values for each reference to them, set only where the storage can be cloned,
+    // see `decode_shared`
+    clone_shared: Option<fn(&Value<I::Storage>) -> Value<I::Storage>>,
+    // accept only the form the writer produces
+    canonical: bool,
 }
 
-impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
+// values and sequence items in `value`
+fn expanded_len<B: AsRef<[u8]> + ByteStorage>(value: &Value<B>) -> usize {
+    let seq = |values: &[Value<B>]| values.iter().map(expanded_len).sum::<usize>();
+    1 + match value {
+        Value::BigUint(le) | Value::BigInt(le) => le.len(),
+        Value::Uints(uints) => uints.len(),
+        Value::Bytes(bytes) | Value::Utf8(bytes) => bytes.as_ref().len(),
+        Value::String(chars) => chars.len(),
+        Value::Tuple(values)
+        | Value::List(_, values)
+        | Value::Struct(_, _, values) => seq(values),
+        Value::Option(_, value) => value.as_deref().map_or(0, expanded_len),
+        Value::Map(_, _, entries) => entries.iter().map(|(key, value)| expanded_len(key) + expanded_len(value)).sum(),
+        Value::Alias(_, _, value)
+        | Value::Choice(_, _, _, value) => expanded_len(value),
+        Value::Uint(_)
+        | Value::Int(_)
+        | Value::Bool(_)
+        | Value::Enum(..)
+        | Value::Type(_)
+        | Value::TypeId(_) => 0,
+    }
+}
+
+impl<'r, B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<'r, I> {
     // begin wrapper impls
 
     #[inline]
-    fn new(bytes: B, max_lens: MaxLens) -> Self {
-        Self { inner: byte_storage::Reader::new(bytes), max_lens }
+    pub(crate) fn new(bytes: B, max_lens: MaxLens) -> Self {
+        Self { inner: byte_storage::Reader::new(bytes), max_lens, tolerance: None, type_table: Vec::new(), shared: Vec::new(), expanded: 0, clone_shared: None, canonical: false }
+    }
+
+    #[inline]
+    fn new_tolerant(bytes: B, max_lens: MaxLens, tolerance: &'r Tolerance<'r>) -> Self {
+        Self { inner: byte_storage::Reader::new(bytes), max_lens, tolerance: Some(tolerance), type_table: Vec::new(), shared: Vec::new(), expanded: 0, clone_shared: None, canonical: false }
     }
 
     #[inline]
@@ -40,7 +85,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
     #[inline]
-    fn into_rest(self) -> I {
+    pub(crate) fn into_rest(self) -> I {
         self.inner.into_rest()
     }
 
@@ -50,13 +95,13 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
     #[inline]
-    fn bytes(&mut self, len: usize) -> Result<B> {
+    pub(crate) fn bytes(&mut self, len: usize) -> Result<B> {
         Ok(self.inner.bytes(len)?)
     }
 
     // end wrapper impls
 
-    fn finish_with<T>(self, res: Result<T>) -> FullResult<T, B> {
+    pub(crate) fn finish_with<T>(self, res: Result<T>) -> FullResult<T, B> {
         match res {
             Ok(val) => {
                 match self.finish() {
@@ -76,7 +121,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
         }
     }
 
-    fn tag(&mut self) -> Result<Tag> {
+    pub(crate) fn tag(&mut self) -> Result<Tag> {
         self.byte()?.try_into()
     }
 
@@ -105,7 +150,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn c_uint<N: NumUnsigned>(&mut self) -> Result<N> {
+    pub(crate) fn c_uint<N: NumUnsigned>(&mut self) -> Result<N> {
         self.uleb128()
     }
 
@@ -115,7 +160,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn c_int<N: NumSigned>(&mut self) -> Result<N> {
+    pub(crate) fn c_int<N: NumSigned>(&mut self) -> Result<N> {
         self.sleb128()
     }
 
@@ -125,7 +170,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn c_bool(&mut self) -> Result<bool> {
+    pub(crate) fn c_bool(&mut self) -> Result<bool> {
         let n: u8 = self.uleb128()?;
         match n {
             0 => Ok(false),
@@ -140,6 +185,26 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
+    pub(crate) fn c_big_uint(&mut self) -> Result<Box<[u8]>> {
+        self.uleb128_big(self.max_lens.big_int)
+    }
+
+    fn v_big_uint(&mut self) -> Result<Box<[u8]>> {
+        self.exp_tag(Tag::BigUint)?;
+        self.c_big_uint()
+    }
+
+
+    pub(crate) fn c_big_int(&mut self) -> Result<Box<[u8]>> {
+        self.sleb128_big(self.max_lens.big_int)
+    }
+
+    fn v_big_int(&mut self) -> Result<Box<[u8]>> {
+        self.exp_tag(Tag::BigInt)?;
+        self.c_big_int()
+    }
+
+
     fn h_uints(&mut self) -> Result<usize> {
         let len: usize = self.uleb128()?;
         if len > self.max_lens.uints {
@@ -163,18 +228,74 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
         alloc_seq(len, |_| self.i_uints())
     }
 
-    fn c_uints(&mut self) -> Result<Box<[u128]>> {
+    pub(crate) fn c_uints(&mut self) -> Result<Box<[u128]>> {
         let len = self.h_uints()?;
         self.ic_uints(len)
     }
 
+    // the first uint, then the differences to the previous ones
+    fn c_uints_delta(&mut self) -> Result<Box<[u128]>> {
+        let len = self.h_uints()?;
+        let mut prev = None;
+        alloc_seq(len, |_| {
+            let n = self.i_uints()?;
+            let n = match prev {
+                Some(prev) => n.checked_add(prev).ok_or(Error::UintsDeltaOverflow)?,
+                None => n,
+            };
+            prev = Some(n);
+            Ok(n)
+        })
+    }
+
+    // the bit width, then the uints in that many bits each, least significant bits first
+    fn c_uints_packed(&mut self) -> Result<Box<[u128]>> {
+        let len = self.h_uints()?;
+        let width: u8 = self.uleb128()?;
+        if width > 128 || (width == 0 && len > 0) {
+            return Err(Error::UintsBitWidth(width));
+        }
+        let width = width as usize;
+        let bits = len.checked_mul(width).ok_or(Error::MaxLen(MaxLenType::Uints, MaxLenExceedValue::Size(len)))?;
+        let bytes = self.bytes(bits.div_ceil(8))?;
+        let bytes = bytes.as_ref();
+        if bits % 8 != 0 && bytes[bits / 8] >> (bits % 8) != 0 {
+            return Err(Error::UintsPackedPadding);
+        }
+        let bit = |i: usize| (bytes[i / 8] >> (i % 8)) & 1;
+        let mut offset = 0;
+        let uints = alloc_seq(len, |_| {
+            let n = (0..width).fold(0, |n, i| n | (bit(offset + i) as u128) << i);
+            offset += width;
+            Ok(n)
+        })?;
+        if self.canonical && writer::uints_width(&uints) as usize != width {
+            return Err(Error::NonCanonical);
+        }
+        Ok(uints)
+    }
+
+    // any of the encodings, but only the one the writer chooses when canonical
+    fn p_uints(&mut self, tag: Tag) -> Result<Box<[u128]>> {
+        let uints = match tag {
+            Tag::Uints => self.c_uints()?,
+            Tag::UintsDelta => self.c_uints_delta()?,
+            Tag::UintsPacked => self.c_uints_packed()?,
+            tag => return Err(Error::ExpectedTypeMismatch { tag, exp_tag: Tag::Uints }),
+        };
+        if self.canonical && writer::uints_tag(&uints) != tag {
+            return Err(Error::NonCanonical);
+        }
+        Ok(uints)
+    }
+
     fn v_uints(&mut self) -> Result<Box<[u128]>> {
-        let len = self.fh_uints()?;
-        self.ic_uints(len)
+        let tag = self.tag()?;
+        self.p_uints(tag)
     }
 
 
-    fn c_bytes(&mut self) -> Result<B> {
+    pub(crate) fn c_bytes(&mut self) -> Result<B> {
         let len: usize = self.uleb128()?;
         if len > self.max_lens.bytes {
             return Err(Error::MaxLen(MaxLenType::Bytes, MaxLenExceedValue::Size(len)))
@@ -212,7 +333,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
         alloc_seq(len, |_| self.i_string())
     }
 
-    fn c_string(&mut self) -> Result<Box<[char]>> {
+    pub(crate) fn c_string(&mut self) -> Result<Box<[char]>> {
         let len = self.h_string()?;
         self.ic_string(len)
     }
@@ -223,7 +344,23 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn h_tuple(&mut self) -> Result<usize> {
+    pub(crate) fn c_utf8(&mut self) -> Result<B> {
+        let len: usize = self.uleb128()?;
+        if len > self.max_lens.utf8 {
+            return Err(Error::MaxLen(MaxLenType::Utf8, MaxLenExceedValue::Size(len)))
+        }
+        let bytes = self.bytes(len)?;
+        let _ = core::str::from_utf8(bytes.as_ref()).map_err(Error::Utf8)?;
+        Ok(bytes)
+    }
+
+    fn v_utf8(&mut self) -> Result<B> {
+        self.exp_tag(Tag::Utf8)?;
+        self.c_utf8()
+    }
+
+
+    pub(crate) fn h_tuple(&mut self) -> Result<usize> {
         let len: usize = self.uleb128()?;
         if len > self.max_lens.tuple {
             return Err(Error::MaxLen(MaxLenType::Tuple, MaxLenExceedValue::Size(len)))
@@ -257,7 +394,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn h_list_items(&mut self) -> Result<usize> {
+    pub(crate) fn h_list_items(&mut self) -> Result<usize> {
         let len: usize = self.uleb128()?;
         if len > self.max_lens.list {
             return Err(Error::MaxLen(MaxLenType::List, MaxLenExceedValue::Size(len)))
@@ -307,7 +444,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn h_list(&mut self) -> Result<OptionWithType<usize>> {
+    pub(crate) fn h_list(&mut self) -> Result<OptionWithType<usize>> {
         self.h_fixed_tuple_like(2)?;
         let is_some = self.v_bool()?;
         Ok(if is_some {
@@ -343,8 +480,21 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
             }
             OptionWithType::Some(len) => {
                 let items = self.ic_list(len)?;
-                let r#type = items.iter().next()
+                let mut iter = items.iter();
+                let first_type = iter.next()
                     .ok_or(Error::EmptyListInNotEmptyMark)?.as_type();
+                // items like empty lists may carry `Unknown`, unify to the most specific one
+                let unified = iter.try_fold(first_type.clone(), |r#type, item| {
+                    let item_type = item.as_type();
+                    Type::unify(&r#type, &item_type).ok_or(Error::ListItemTypeMismatch { r#type, item_type })
+                });
+                // items that do not unify are only rejected when canonical, otherwise the list
+                // has the type of the first item as it always had
+                let r#type = match unified {
+                    Ok(r#type) => r#type,
+                    Err(err) if self.canonical => return Err(err),
+                    Err(_) => first_type,
+                };
                 (r#type, items)
             }
         })
@@ -361,7 +511,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn h_option(&mut self) -> Result<OptionWithType<()>> {
+    pub(crate) fn h_option(&mut self) -> Result<OptionWithType<()>> {
         self.h_fixed_tuple_like(2)?;
         let is_some = self.v_bool()?;
         Ok(if is_some {
@@ -400,7 +550,98 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn h_alias(&mut self) -> Result<(TypeId, Box<[Type]>)> {
+    pub(crate) fn h_map_entries(&mut self) -> Result<usize> {
+        let len: usize = self.uleb128()?;
+        if len > self.max_lens.map {
+            return Err(Error::MaxLen(MaxLenType::Map, MaxLenExceedValue::Size(len)))
+        }
+        Ok(len)
+    }
+
+    pub(crate) fn h_map(&mut self) -> Result<OptionWithType<usize, (Type, Type)>> {
+        self.h_fixed_tuple_like(2)?;
+        let is_some = self.v_bool()?;
+        Ok(if is_some {
+            self.exp_tag(Tag::ListItems)?;
+            let len = self.h_map_entries()?;
+            if len == 0 {
+                return Err(Error::EmptyListInNotEmptyMark);
+            }
+            OptionWithType::Some(len)
+        } else {
+            self.exp_tag(Tag::Generics)?;
+            self.h_fixed_tuple_like(2)?;
+            let key_type = self.v_type()?;
+            let value_type = self.v_type()?;
+            OptionWithType::None((key_type, value_type))
+        })
+    }
+
+    fn fh_map(&mut self) -> Result<OptionWithType<usize, (Type, Type)>> {
+        self.exp_tag(Tag::Map)?;
+        self.h_map()
+    }
+
+    #[inline]
+    fn i_map(&mut self) -> Result<(Value<B>, Value<B>)> {
+        let key = self.value()?;
+        let value = self.value()?;
+        Ok((key, value))
+    }
+
+    #[inline]
+    fn ic_map(&mut self, len: usize) -> Result<Box<[(Value<B>, Value<B>)]>> {
+        alloc_seq(len, |_| self.i_map())
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn p_map(&mut self, prim: OptionWithType<usize, (Type, Type)>) -> Result<(Type, Type, Box<[(Value<B>, Value<B>)]>)> {
+        Ok(match prim {
+            OptionWithType::None((key_type, value_type)) => {
+                (key_type, value_type, Box::new([]))
+            }
+            OptionWithType::Some(len) => {
+                let entries = self.ic_map(len)?;
+                let mut iter = entries.iter();
+                let (first_key, first_value) = iter.next()
+                    .ok_or(Error::EmptyListInNotEmptyMark)?;
+                let (mut key_type, mut value_type) = (first_key.as_type(), first_value.as_type());
+                let mut prev_key = first_key;
+                for (key, value) in iter {
+                    match prev_key.canonical_cmp(key) {
+                        core::cmp::Ordering::Less => {}
+                        core::cmp::Ordering::Equal => return Err(Error::DuplicateMapKey),
+                        core::cmp::Ordering::Greater => return Err(Error::UnorderedMapKeys),
+                    }
+                    prev_key = key;
+                    let (entry_key_type, entry_value_type) = (key.as_type(), value.as_type());
+                    match (Type::unify(&key_type, &entry_key_type), Type::unify(&value_type, &entry_value_type)) {
+                        (Some(key_type2), Some(value_type2)) => (key_type, value_type) = (key_type2, value_type2),
+                        _ => return Err(Error::MapEntryTypeMismatch {
+                            r#type: Type::Map(Box::new(key_type), Box::new(value_type)),
+                            entry_type: Type::Map(Box::new(entry_key_type), Box::new(entry_value_type)),
+                        }),
+                    }
+                }
+                (key_type, value_type, entries)
+            }
+        })
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn c_map(&mut self) -> Result<(Type, Type, Box<[(Value<B>, Value<B>)]>)> {
+        let prim = self.h_map()?;
+        self.p_map(prim)
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn v_map(&mut self) -> Result<(Type, Type, Box<[(Value<B>, Value<B>)]>)> {
+        let prim = self.fh_map()?;
+        self.p_map(prim)
+    }
+
+
+    pub(crate) fn h_alias(&mut self) -> Result<(TypeId, Box<[Type]>)> {
         self.h_fixed_tuple_like(3)?;
         let type_id = self.v_type_id()?;
         let generics = self.v_generics()?;
@@ -412,20 +653,55 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
         self.h_alias()
     }
 
+    // payloads of the fixed-width integer aliases are checked against their widths
+    fn p_uint<N: NumUnsigned>(&mut self, tagged: bool) -> Result<N> {
+        if tagged { self.v_uint() } else { self.c_uint() }
+    }
+
+    fn p_int<N: NumSigned>(&mut self, tagged: bool) -> Result<N> {
+        if tagged { self.v_int() } else { self.c_int() }
+    }
+
+    // payloads of fixed-width integer aliases, read as the native integers so that ones out of
+    // range are rejected, `None` for other aliases
+    pub(crate) fn p_fixed_width(&mut self, type_id: &TypeId, generics: &[Type], tagged: bool) -> Result<Option<Value<B>>> {
+        if !generics.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(match *type_id {
+            well_known::U8 => Value::Uint(self.p_uint::<u8>(tagged)?.into()),
+            well_known::U16 => Value::Uint(self.p_uint::<u16>(tagged)?.into()),
+            well_known::U32 => Value::Uint(self.p_uint::<u32>(tagged)?.into()),
+            well_known::U64 => Value::Uint(self.p_uint::<u64>(tagged)?.into()),
+            well_known::I8 => Value::Int(self.p_int::<i8>(tagged)?.into()),
+            well_known::I16 => Value::Int(self.p_int::<i16>(tagged)?.into()),
+            well_known::I32 => Value::Int(self.p_int::<i32>(tagged)?.into()),
+            well_known::I64 => Value::Int(self.p_int::<i64>(tagged)?.into()),
+            _ => return Ok(None),
+        }))
+    }
+
+    fn p_alias(&mut self, type_id: &TypeId, generics: &[Type]) -> Result<Value<B>> {
+        match self.p_fixed_width(type_id, generics, true)? {
+            Some(value) => Ok(value),
+            None => self.value(),
+        }
+    }
+
     fn c_alias(&mut self) -> Result<(TypeId, Box<[Type]>, Value<B>)> {
         let (type_id, generics) = self.h_alias()?;
-        let value = self.value()?;
+        let value = self.p_alias(&type_id, &generics)?;
         Ok((type_id, generics, value))
     }
 
     fn v_alias(&mut self) -> Result<(TypeId, Box<[Type]>, Value<B>)> {
         let (type_id, generics) = self.fh_alias()?;
-        let value = self.value()?;
+        let value = self.p_alias(&type_id, &generics)?;
         Ok((type_id, generics, value))
     }
 
 
-    fn c_enum(&mut self) -> Result<(TypeId, VariantId)> {
+    pub(crate) fn c_enum(&mut self) -> Result<(TypeId, VariantId)> {
         self.h_fixed_tuple_like(2)?;
         let type_id = self.v_type_id()?;
         let variant_id: VariantId = self.v_uint()?;
@@ -441,7 +717,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn h_choice(&mut self) -> Result<(TypeId, Box<[Type]>, VariantId)> {
+    pub(crate) fn h_choice(&mut self) -> Result<(TypeId, Box<[Type]>, VariantId)> {
         self.h_fixed_tuple_like(4)?;
         let type_id = self.v_type_id()?;
         let generics = self.v_generics()?;
@@ -472,7 +748,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
     }
 
 
-    fn h_struct(&mut self) -> Result<(TypeId, Box<[Type]>, usize)> {
+    pub(crate) fn h_struct(&mut self) -> Result<(TypeId, Box<[Type]>, usize)> {
         self.h_fixed_tuple_like(3)?;
         let type_id = self.v_type_id()?;
         let generics = self.v_generics()?;
@@ -485,25 +761,28 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
         self.h_struct()
     }
 
-    fn i_struct(&mut self) -> Result<Value<B>> {
+    pub(crate) fn i_struct(&mut self) -> Result<Value<B>> {
         self.value()
     }
 
-    fn ic_struct(&mut self, len: usize) -> Result<Box<[Value<B>]>> {
-        alloc_seq(len, |_| self.i_struct())
+    fn ic_struct(&mut self, type_id: &TypeId, generics: &[Type], len: usize) -> Result<Box<[Value<B>]>> {
+        match self.tolerance {
+            Some(tolerance) => self.ic_struct_tolerant(tolerance, type_id, generics, len),
+            None => alloc_seq(len, |_| self.i_struct()),
+        }
     }
 
     #[allow(clippy::type_complexity)]
     fn c_struct(&mut self) -> Result<(TypeId, Box<[Type]>, Box<[Value<B>]>)> {
         let (type_id, generics, len) = self.h_struct()?;
-        let values = self.ic_struct(len)?;
+        let values = self.ic_struct(&type_id, &generics, len)?;
         Ok((type_id, generics, values))
     }
 
     #[allow(clippy::type_complexity)]
     fn v_struct(&mut self) -> Result<(TypeId, Box<[Type]>, Box<[Value<B>]>)> {
         let (type_id, generics, len) = self.fh_struct()?;
-        let values = self.ic_struct(len)?;
+        let values = self.ic_struct(&type_id, &generics, len)?;
         Ok((type_id, generics, values))
     }
 
@@ -550,9 +829,12 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
                 Uint
                 Int
                 Bool
+                BigUint
+                BigInt
                 Uints
                 Bytes
                 String
+                Utf8
                 Type
                 TypeId
             }
@@ -568,6 +850,16 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
                 Choice
                 Struct
             }
+            TypeTag::Param => {
+                let index: u32 = self.v_uint()?;
+                Type::Param(index)
+            }
+            TypeTag::Map => {
+                self.fh_fixed_tuple(2)?;
+                let key_type = self.v_type()?;
+                let value_type = self.v_type()?;
+                Type::Map(Box::new(key_type), Box::new(value_type))
+            }
             TypeTag::Tuple => {
                 let len = self.fh_tuple()?;
                 if len > self.max_lens.tuple {
@@ -579,9 +871,15 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
         })
     }
 
-    fn v_type(&mut self) -> Result<Type> {
-        self.exp_tag(Tag::Type)?;
-        self.c_type()
+    pub(crate) fn v_type(&mut self) -> Result<Type> {
+        match self.tag()? {
+            Tag::Type => self.c_type(),
+            Tag::TypeRef => match self.c_type_ref()? {
+                TypeTableEntry::Type(r#type) => Ok(r#type.clone()),
+                TypeTableEntry::TypeId(_) => Err(Error::ExpectedTypeMismatch { tag: Tag::TypeId, exp_tag: Tag::Type }),
+            },
+            tag => Err(Error::ExpectedTypeMismatch { tag, exp_tag: Tag::Type }),
+        }
     }
 
 
@@ -597,24 +895,230 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
                 let id: u128 = self.v_uint()?;
                 TypeId::Std(id)
             }
+            TypeIdTag::ThirdParty => {
+                self.fh_fixed_tuple(2)?;
+                let namespace: u128 = self.v_uint()?;
+                let id: u128 = self.v_uint()?;
+                TypeId::ThirdParty(namespace, id)
+            }
         })
     }
 
-    fn v_type_id(&mut self) -> Result<TypeId> {
-        self.exp_tag(Tag::TypeId)?;
-        self.c_type_id()
+    pub(crate) fn v_type_id(&mut self) -> Result<TypeId> {
+        match self.tag()? {
+            Tag::TypeId => self.c_type_id(),
+            Tag::TypeRef => match self.c_type_ref()? {
+                TypeTableEntry::TypeId(type_id) => Ok(type_id.clone()),
+                TypeTableEntry::Type(_) => Err(Error::ExpectedTypeMismatch { tag: Tag::Type, exp_tag: Tag::TypeId }),
+            },
+            tag => Err(Error::ExpectedTypeMismatch { tag, exp_tag: Tag::TypeId }),
+        }
     }
 
 
+    // entries may refer to the ones before them
+    fn c_type_table(&mut self) -> Result<()> {
+        if self.canonical {
+            return Err(Error::NonCanonical);
+        }
+        let len: usize = self.uleb128()?;
+        if len > self.max_lens.type_table {
+            return Err(Error::MaxLen(MaxLenType::TypeTable, MaxLenExceedValue::Size(len)))
+        }
+        for _ in 0..len {
+            let entry = match self.tag()? {
+                Tag::Type => TypeTableEntry::Type(self.c_type()?),
+                Tag::TypeId => TypeTableEntry::TypeId(self.c_type_id()?),
+                tag => return Err(Error::ExpectedTypeMismatch { tag, exp_tag: Tag::Type }),
+            };
+            self.type_table.push(entry);
+        }
+        Ok(())
+    }
+
+    fn c_type_ref(&mut self) -> Result<&TypeTableEntry> {
+        if self.canonical {
+            return Err(Error::NonCanonical);
+        }
+        let index: usize = self.uleb128()?;
+        self.type_table.get(index).ok_or(Error::UnknownTypeRef(index))
+    }
+
+    // shared values are copied for each reference to them
+    fn c_shared(&mut self) -> Result<Value<B>> {
+        if self.canonical {
+            return Err(Error::NonCanonical);
+        }
+        let Some(clone) = self.clone_shared else {
+            return Err(Error::UnexpectedShared);
+        };
+        self.h_fixed_tuple_like(2)?;
+        if self.v_bool()? {
+            let value = self.value()?;
+            self.shared.push((clone(&value), expanded_len(&value)));
+            Ok(value)
+        } else {
+            let index: usize = self.v_uint()?;
+            let (value, len) = self.shared.get(index).ok_or(Error::UnknownSharedRef(index))?;
+            self.expanded = self.expanded.saturating_add(*len);
+            if self.expanded > self.max_lens.expansion {
+                return Err(Error::MaxLen(MaxLenType::Expansion, MaxLenExceedValue::Size(self.expanded)))
+            }
+            Ok(clone(value))
+        }
+    }
+
+    /// Reads past a value without building it, checking its structure but not the
+    /// consistency of item types, map key order or fixed-width alias payloads.
+    pub(crate) fn skip_value(&mut self) -> Result<()> {
+        let tag = self.tag()?;
+        let _ = self.c_skip(tag)?;
+        Ok(())
+    }
+
+    fn skip_values(&mut self, len: usize) -> Result<()> {
+        for _ in 0..len {
+            self.skip_value()?;
+        }
+        Ok(())
+    }
+
+    // hands the tag back
+    pub(crate) fn c_skip(&mut self, tag: Tag) -> Result<Tag> {
+        match tag {
+            Tag::Uint => {
+                let _: u128 = self.c_uint()?;
+            }
+            Tag::Int => {
+                let _: i128 = self.c_int()?;
+            }
+            Tag::Bool => {
+                let _ = self.c_bool()?;
+            }
+            Tag::BigUint => {
+                let _ = self.c_big_uint()?;
+            }
+            Tag::BigInt => {
+                let _ = self.c_big_int()?;
+            }
+            Tag::Uints => {
+                let len = self.h_uints()?;
+                for _ in 0..len {
+                    let _ = self.i_uints()?;
+                }
+            }
+            Tag::UintsDelta => {
+                let _ = self.c_uints_delta()?;
+            }
+            Tag::UintsPacked => {
+                let _ = self.c_uints_packed()?;
+            }
+            Tag::Bytes => {
+                let _ = self.c_bytes()?;
+            }
+            Tag::String => {
+                let len = self.h_string()?;
+                for _ in 0..len {
+                    let _ = self.i_string()?;
+                }
+            }
+            Tag::Utf8 => {
+                let _ = self.c_utf8()?;
+            }
+            Tag::Tuple => {
+                let len = self.h_tuple()?;
+                self.skip_values(len)?;
+            }
+            Tag::List => {
+                if let OptionWithType::Some(len) = self.h_list()? {
+                    self.skip_values(len)?;
+                }
+            }
+            Tag::Option => {
+                if let OptionWithType::Some(()) = self.h_option()? {
+                    self.skip_value()?;
+                }
+            }
+            Tag::Map => {
+                if let OptionWithType::Some(len) = self.h_map()? {
+                    for _ in 0..len {
+                        self.skip_value()?;
+                        self.skip_value()?;
+                    }
+                }
+            }
+            Tag::Alias => {
+                let _ = self.h_alias()?;
+                self.skip_value()?;
+            }
+            Tag::Enum => {
+                let _ = self.c_enum()?;
+            }
+            Tag::Choice => {
+                let _ = self.h_choice()?;
+                self.skip_value()?;
+            }
+            Tag::Struct => {
+                let (_, _, len) = self.h_struct()?;
+                self.skip_values(len)?;
+            }
+            Tag::Type => {
+                let _ = self.c_type()?;
+            }
+            Tag::TypeId => {
+                let _ = self.c_type_id()?;
+            }
+            Tag::TypeRef => {
+                let _ = self.c_type_ref()?;
+            }
+            Tag::Shared => {
+                self.h_fixed_tuple_like(2)?;
+                if self.v_bool()? {
+                    self.skip_value()?;
+                } else {
+                    let _: usize = self.v_uint()?;
+                }
+            }
+            Tag::TypeTable => {
+                return Err(Error::TypeTableNotOnTop);
+            }
+            tag @ (
+                Tag::ListItems
+                | Tag::Generics
+            )  => {
+                return Err(Error::ImplicitTypeOnTop(tag));
+            }
+        }
+        Ok(tag)
+    }
+
+    /// A value, preceded by a type table if there is one.
+    pub(crate) fn document(&mut self) -> Result<Value<B>> {
+        let tag = self.tag()?;
+        if tag == Tag::TypeTable {
+            self.c_type_table()?;
+            self.value()
+        } else {
+            self.c_value(tag)
+        }
+    }
+
     pub fn value(&mut self) -> Result<Value<B>> {
         let tag = self.tag()?;
+        self.c_value(tag)
+    }
+
+    fn c_value(&mut self, tag: Tag) -> Result<Value<B>> {
         Ok(match tag {
             Tag::Uint => Value::Uint(self.c_uint()?),
             Tag::Int => Value::Int(self.c_int()?),
             Tag::Bool => Value::Bool(self.c_bool()?),
-            Tag::Uints => Value::Uints(self.c_uints()?),
+            Tag::BigUint => Value::BigUint(self.c_big_uint()?),
+            Tag::BigInt => Value::BigInt(self.c_big_int()?),
+            Tag::Uints | Tag::UintsDelta | Tag::UintsPacked => Value::Uints(self.p_uints(tag)?),
             Tag::Bytes => Value::Bytes(self.c_bytes()?),
             Tag::String => Value::String(self.c_string()?),
+            Tag::Utf8 => Value::Utf8(self.c_utf8()?),
             Tag::Tuple => Value::Tuple(self.c_tuple()?),
             Tag::List => {
                 let (r#type, values) = self.c_list()?;
@@ -624,6 +1128,10 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
                 let (r#type, value) = self.c_option()?;
                 Value::Option(r#type, value.map(Box::new))
             }
+            Tag::Map => {
+                let (key_type, value_type, entries) = self.c_map()?;
+                Value::Map(key_type, value_type, entries)
+            }
             Tag::Alias => {
                 let (type_id, generics, value) = self.c_alias()?;
                 Value::Alias(type_id, generics, Box::new(value))
@@ -642,6 +1150,14 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
             },
             Tag::Type => Value::Type(self.c_type()?),
             Tag::TypeId => Value::TypeId(self.c_type_id()?),
+            Tag::TypeRef => match self.c_type_ref()? {
+                TypeTableEntry::Type(r#type) => Value::Type(r#type.clone()),
+                TypeTableEntry::TypeId(type_id) => Value::TypeId(type_id.clone()),
+            },
+            Tag::TypeTable => {
+                return Err(Error::TypeTableNotOnTop);
+            }
+            Tag::Shared => self.c_shared()?,
             tag @ (
                 Tag::ListItems
                 | Tag::Generics
@@ -656,7 +1172,7 @@ impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<I> {
 impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
     pub fn decode_with_max_lens<I: Input<Storage = B>>(buf: B, max_lens: MaxLens) -> FullResult<Value<B>, B> {
         let mut reader = Reader::<I>::new(buf, max_lens);
-        let val = reader.value();
+        let val = reader.document();
         reader.finish_with(val)
     }
 
@@ -667,7 +1183,7 @@ impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
     // cannot return FullResult
     pub fn decode_first_value_with_max_lens<I: Input<Storage = B>>(buf: B, max_lens: MaxLens) -> (Result<Value<B>>, B) {
         let mut reader = Reader::<I>::new(buf, max_lens);
-        let res = reader.value();
+        let res = reader.document();
         (res, reader.into_rest().leak())
     }
 
@@ -675,4 +1191,49 @@ impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
     pub fn decode_first_value<I: Input<Storage = B>>(buf: B) -> (Result<Value<B>>, B) {
         Self::decode_first_value_with_max_lens::<I>(buf, DEFAULT_MAX_LENS)
     }
+
+    pub fn decode_canonical_with_max_lens<I: Input<Storage = B>>(buf: B, max_lens: MaxLens) -> FullResult<Value<B>, B> {
+        let mut reader = Reader::<I>::new(buf, max_lens);
+        reader.canonical = true;
+        let val = reader.document();
+        reader.finish_with(val)
+    }
+
+    /// Like `decode`, but accepts only the one form `encode` writes for each value: without
+    /// type tables or shared values, and with `Value::Uints` in the encoding the writer chooses.
+    /// LEB128s, map key order and alias payloads are checked by `decode` as well.
+    pub fn decode_canonical<I: Input<Storage = B>>(buf: B) -> FullResult<Value<B>, B> {
+        Self::decode_canonical_with_max_lens::<I>(buf, DEFAULT_MAX_LENS)
+    }
+
+    pub fn is_canonical<I: Input<Storage = B>>(buf: B) -> bool {
+        Self::decode_canonical::<I>(buf).is_ok()
+    }
+
+    pub fn decode_tolerant_with_max_lens<I: Input<Storage = B>>(buf: B, max_lens: MaxLens, tolerance: &Tolerance) -> FullResult<Value<B>, B> {
+        let mut reader = Reader::<I>::new_tolerant(buf, max_lens, tolerance);
+        let val = reader.document();
+        reader.finish_with(val)
+    }
+
+    /// Decodes structs registered in `tolerance.registry` with older or newer layouts,
+    /// see `Tolerance`.
+    pub fn decode_tolerant<I: Input<Storage = B>>(buf: B, tolerance: &Tolerance) -> FullResult<Value<B>, B> {
+        Self::decode_tolerant_with_max_lens::<I>(buf, DEFAULT_MAX_LENS, tolerance)
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage + Clone> Value<B> {
+    pub fn decode_shared_with_max_lens<I: Input<Storage = B>>(buf: B, max_lens: MaxLens) -> FullResult<Value<B>, B> {
+        let mut reader = Reader::<I>::new(buf, max_lens);
+        reader.clone_shared = Some(Value::clone);
+        let val = reader.document();
+        reader.finish_with(val)
+    }
+
+    /// Like `decode`, also accepting the shared values `encode_shared` writes, which are copied
+    /// for each reference to them, see `MaxLens::expansion`. Other decoders reject them.
+    pub fn decode_shared<I: Input<Storage = B>>(buf: B) -> FullResult<Value<B>, B> {
+        Self::decode_shared_with_max_lens::<I>(buf, DEFAULT_MAX_LENS)
+    }
 }
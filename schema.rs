@@ -0,0 +1,119 @@
+use alloc::collections::BTreeMap;
+use super::*;
+
+/// Value filled in for a field missing from data written with an older definition.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldDefault {
+    /// Default of the field type, see `Registry::default_value`.
+    Zero,
+    Uint(u128),
+    Int(i128),
+    Bool(bool),
+    String(Box<[char]>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: Box<str>,
+    pub r#type: Type,
+    pub default: Option<FieldDefault>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EnumVariant {
+    pub id: VariantId,
+    pub name: Box<str>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChoiceVariant {
+    pub id: VariantId,
+    pub name: Box<str>,
+    pub r#type: Type,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DefKind {
+    Alias(Type),
+    Enum(Box<[EnumVariant]>),
+    Choice(Box<[ChoiceVariant]>),
+    Struct(Box<[Field]>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Def {
+    pub name: Box<str>,
+    pub type_id: TypeId,
+    pub generics: Box<[Box<str>]>,
+    pub kind: DefKind,
+}
+
+impl Def {
+    /// The `Type` referring to this definition, instantiated with `generics`.
+    pub fn as_type(&self, generics: Box<[Type]>) -> Type {
+        let type_id = self.type_id.clone();
+        match self.kind {
+            DefKind::Alias(_) => Type::Alias(type_id, generics),
+            DefKind::Enum(_) => Type::Enum(type_id),
+            DefKind::Choice(_) => Type::Choice(type_id, generics),
+            DefKind::Struct(_) => Type::Struct(type_id, generics),
+        }
+    }
+
+    pub fn field(&self, name: &str) -> Option<(usize, &Field)> {
+        match &self.kind {
+            DefKind::Struct(fields) => fields.iter().enumerate().find(|(_, field)| &*field.name == name),
+            _ => None,
+        }
+    }
+
+    pub fn choice_variant(&self, id: VariantId) -> Option<&ChoiceVariant> {
+        match &self.kind {
+            DefKind::Choice(variants) => variants.iter().find(|variant| variant.id == id),
+            _ => None,
+        }
+    }
+
+    pub fn enum_variant(&self, id: VariantId) -> Option<&EnumVariant> {
+        match &self.kind {
+            DefKind::Enum(variants) => variants.iter().find(|variant| variant.id == id),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Registry {
+    defs: BTreeMap<TypeId, Def>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry { defs: BTreeMap::new() }
+    }
+
+    /// Returns the replaced definition if `def.type_id` was already registered.
+    pub fn insert(&mut self, def: Def) -> Option<Def> {
+        self.defs.insert(def.type_id.clone(), def)
+    }
+
+    pub fn get(&self, type_id: &TypeId) -> Option<&Def> {
+        self.defs.get(type_id)
+    }
+
+    pub fn get_by_name(&self, name: &str) -> Option<&Def> {
+        self.defs.values().find(|def| &*def.name == name)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Def> {
+        self.defs.values()
+    }
+
+    pub fn len(&self) -> usize {
+        self.defs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.defs.is_empty()
+    }
+}
@@ -58,17 +58,17 @@ fn case_2() -> Value<&'static [u8]> {
         Value::Int(-7777777),
         Value::Uint(1027),
         Value::Uints(seq![11, 12, 1314, 1516171819, 20]),
-        Value::Alias(TypeId::Std(10), seq![], Box::new(Value::Bytes(F64_BYTES))),
+        Value::Alias(well_known::F64, seq![], Box::new(Value::Bytes(F64_BYTES))),
         Value::String(s("Berylsoft")),
         Value::Bytes(b(b"(\x00)")),
         Value::Option(Type::String, None),
         Value::Option(Type::Bool, Some(Box::new(Value::Bool(true)))),
-        Value::Alias(TypeId::Anonymous/* third-party */, seq![], Box::new(Value::Bytes(b(b"\xff")))),
+        Value::Alias(TypeId::ThirdParty(0xbe, 0x01), seq![], Box::new(Value::Bytes(b(b"\xff")))),
         Value::Enum(TypeId::Std(0x5f50), 11),
         Value::Choice(TypeId::Std(0x5f49), seq![], 5, Box::new(Value::Int(5))),
         Value::Choice(TypeId::Std(0xfe00aa), seq![Type::Alias(TypeId::Std(0xfe00bb), seq![Type::Uint])], 163, Box::new(Value::Uint(12))),
         Value::Type(Type::List(Box::new(Type::List(Box::new(Type::Struct(TypeId::Anonymous, seq![])))))),
-        Value::TypeId(TypeId::Std(0xfedcba98765432/* third-party */)),
+        Value::TypeId(TypeId::ThirdParty(0xfedcba, 0x98765432)),
         Value::Option(
             Type::Tuple(seq![Type::Int, Type::Tuple(seq![Type::Bytes]), Type::Bool]),
             Some(Box::new(Value::Tuple(seq![Value::Int(9), Value::Tuple(seq![Value::Bytes(b(b"\xab"))]), Value::Bool(true)])))
@@ -136,8 +136,10 @@ fn cases() {
                 46 01
             41 03
                 44 02
-                    55 78
-                    50 00
+                    55 7a
+                    50 02
+                        55 be01
+                        55 01
                 47 00
                 42 01 ff
             45 02
@@ -181,8 +183,10 @@ fn cases() {
                                 50 00
                             47 00
             44 02
-                55 79
-                55 b2a8d9c3a997b77f
+                55 7a
+                50 02
+                    55 bab9fb07
+                    55 b2a8d9c309
             4f 02
                 46 01
                 50 03
@@ -333,5 +337,1114 @@ fn text_cases() {
 
     case(case_1(), r#"L(F1 M(P(U123 L(F1 M(S"hello" S"goodbye"))) P(U999999 L(F1 M(S"how are you" S"fine" S"thanks")))))"#);
 
-    case(case_2(), r#"P(P() F0 I-7777777 U1027 N[11 12 1314 1516171819 20] A(D(U'y' U0x0a) G() B"0000000000004940") S"Berylsoft" B"280029" O(F0 T(U's' P())) O(F1 F1) A(D(U'x' P()) G() B"ff") E(D(U'y' U0x5f50) U11) C(D(U'y' U0x5f49) G() U5 I5) C(D(U'y' U0xfe00aa) G(T(U'a' P(U0xfe00bb G(T(U'u' P()))))) U163 U12) T(U'l' T(U'l' T(U'r' P(U'x' G())))) D(U'y' U0xfedcba98765432) O(F1 P(I9 P(B"ab") F1)))"#);
+    case(case_2(), r#"P(P() F0 I-7777777 U1027 N[11 12 1314 1516171819 20] @f64"50" S"Berylsoft" B"280029" O(F0 T(U's' P())) O(F1 F1) A(D(U'z' P(U0xbe U0x01)) G() B"ff") E(D(U'y' U0x5f50) U11) C(D(U'y' U0x5f49) G() U5 I5) C(D(U'y' U0xfe00aa) G(T(U'a' P(D(U'y' U0xfe00bb) G(T(U'u' P()))))) U163 U12) T(U'l' T(U'l' T(U'r' P(D(U'x' P()) G())))) D(U'z' P(U0xfedcba U0x98765432)) O(F1 P(I9 P(B"ab") F1)))"#);
+}
+
+#[test]
+fn idl() {
+    use crate::schema::*;
+
+    let registry = Registry::parse("
+        // comments are allowed
+        struct User = 0x1001 <T> { id: u, name: s, tags: l<s>, extra: T }
+        choice Shape = 0x1002 { 0 Circle(u), 1 Rect(p<u u>) }
+        enum Color = 0x1003 { 0 Red, 1 Green }
+        alias Time = 0x1004 b
+        struct Canvas = 0x1005 { shapes: l<Shape>, color: o<Color>, owner: User<Time> }
+    ").unwrap();
+    assert_eq!(registry.len(), 5);
+
+    let shape = registry.get(&TypeId::Std(0x1002)).unwrap();
+    assert_eq!(&*shape.name, "Shape");
+    assert_eq!(shape.choice_variant(1).unwrap().r#type, Type::Tuple(seq![Type::Uint, Type::Uint]));
+
+    let canvas = registry.get_by_name("Canvas").unwrap();
+    assert_eq!(canvas.kind, DefKind::Struct(seq![
+        Field { name: "shapes".into(), r#type: Type::List(Box::new(Type::Choice(TypeId::Std(0x1002), seq![]))), default: None },
+        Field { name: "color".into(), r#type: Type::Option(Box::new(Type::Enum(TypeId::Std(0x1003)))), default: None },
+        Field { name: "owner".into(), r#type: Type::Struct(TypeId::Std(0x1001), seq![Type::Alias(TypeId::Std(0x1004), seq![])]), default: None },
+    ]));
+
+    let err = Registry::parse("struct A = 1 { x: B }").unwrap_err();
+    assert_eq!(err, crate::idl::ParseError { kind: crate::idl::ParseErrorKind::UnknownType("B".into()), pos: 18 });
+
+    use crate::idl::{ParseError, ParseErrorKind::*};
+    let err = |src| Registry::parse(src).unwrap_err();
+    assert_eq!(err("struct A = _ { x: u }"), ParseError { kind: ExpectedNumber, pos: 11 });
+    assert_eq!(err("struct A = 0x__ { x: u }"), ParseError { kind: ExpectedNumber, pos: 11 });
+    assert_eq!(err("enum A = 1 <T> { 0 X }"), ParseError { kind: EnumGenerics("A".into()), pos: 5 });
+    assert_eq!(err("struct u = 1 { x: u }"), ParseError { kind: ReservedName("u".into()), pos: 7 });
+    assert_eq!(err("alias l = 1 u"), ParseError { kind: ReservedName("l".into()), pos: 6 });
+    assert_eq!(err("struct A = 1 <T s> { x: T }"), ParseError { kind: ReservedName("s".into()), pos: 16 });
+    assert_eq!(err("enum A = 1 { 0 X, 1 X }"), ParseError { kind: DuplicateVariantName("X".into()), pos: 5 });
+    assert_eq!(err("choice A = 1 { 0 X(u), 1 X(s) }"), ParseError { kind: DuplicateVariantName("X".into()), pos: 7 });
+    assert_eq!(err("struct A = 1 <T T> { x: T }"), ParseError { kind: DuplicateParam("T".into()), pos: 7 });
+    assert_eq!(Registry::parse("struct A = 1_0 { x: u }").unwrap().get_by_name("A").unwrap().type_id, TypeId::Std(10));
+}
+
+#[test]
+fn check_generics() {
+    use crate::{schema::*, check::CheckError};
+
+    let registry = Registry::parse("
+        struct Pair = 0x2001 <K V> { key: K, value: l<V> }
+        alias Id = 0x2002 u
+    ").unwrap();
+    let pair = registry.get(&TypeId::Std(0x2001)).unwrap();
+    let DefKind::Struct(fields) = &pair.kind else { panic!() };
+    assert_eq!(fields[1].r#type, Type::List(Box::new(Type::Param(1))));
+    assert_eq!(
+        fields[1].r#type.substitute(&[Type::Uint, Type::String]),
+        Some(Type::List(Box::new(Type::String))),
+    );
+    assert_eq!(fields[1].r#type.substitute(&[Type::Uint]), None);
+
+    let id = Type::Alias(TypeId::Std(0x2002), seq![]);
+    let value = |key: Value<&'static [u8]>| Value::Struct(TypeId::Std(0x2001), seq![id.clone(), Type::String], seq![
+        key,
+        Value::List(Type::String, seq![Value::String(s("a"))]),
+    ]);
+
+    registry.check(&value(Value::Alias(TypeId::Std(0x2002), seq![], Box::new(Value::Uint(1))))).unwrap();
+    assert_eq!(
+        registry.check(&value(Value::Uint(1))),
+        Err(CheckError::TypeMismatch { r#type: Type::Uint, exp_type: id.clone() }),
+    );
+    assert_eq!(
+        registry.check(&value(Value::Alias(TypeId::Std(0x2002), seq![], Box::new(Value::Int(1))))),
+        Err(CheckError::TypeMismatch { r#type: Type::Int, exp_type: Type::Uint }),
+    );
+}
+
+#[test]
+fn unify() {
+    let unknown_list = || Value::List(Type::Unknown, seq![]);
+    let strings = Type::List(Box::new(Type::String));
+
+    assert_eq!(Type::unify(&Type::Tuple(seq![Type::Unknown, Type::Uint]), &Type::Tuple(seq![Type::Bool, Type::Unknown])), Some(Type::Tuple(seq![Type::Bool, Type::Uint])));
+    assert_eq!(Type::unify(&Type::Uint, &Type::Int), None);
+    assert!(Type::List(Box::new(Type::Unknown)).is_assignable_to(&strings));
+
+    let v: Value<&'static [u8]> = Value::List(strings.clone(), seq![
+        unknown_list(),
+        Value::List(Type::String, seq![Value::String(s("a"))]),
+    ]);
+    let buf = v.encode::<VecOutput>();
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+    assert_eq!(Value::list(Type::Unknown, [unknown_list(), Value::List(Type::String, seq![Value::String(s("a"))])]), Some(v));
+    assert_eq!(Value::<&'static [u8]>::list(strings.clone(), [unknown_list()]), Some(Value::List(strings.clone(), seq![unknown_list()])));
+    assert_eq!(Value::<&'static [u8]>::list(Type::Uint, [Value::Int(1)]), None);
+
+    // items that do not unify are kept as before, but have no canonical form
+    let v = Value::<&'static [u8]>::List(Type::Uint, seq![Value::Uint(1), Value::Int(1)]);
+    let buf = v.encode::<VecOutput>();
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+    assert_eq!(
+        Value::decode_canonical::<SliceInput>(&buf).unwrap_err().err,
+        Error::ListItemTypeMismatch { r#type: Type::Uint, item_type: Type::Int },
+    );
+
+    let registry = crate::schema::Registry::parse("struct Tags = 0x3001 { tags: l<s> }").unwrap();
+    registry.check(&Value::<&'static [u8]>::Struct(TypeId::Std(0x3001), seq![], seq![unknown_list()])).unwrap();
+}
+
+#[test]
+fn compat() {
+    use crate::{schema::Registry, compat::*};
+
+    let old = Registry::parse("
+        struct User = 0x4001 { id: u, name: s }
+        choice Shape = 0x4002 { 0 Circle(u), 1 Rect(p<u u>) }
+    ").unwrap();
+    let new = Registry::parse("
+        struct User = 0x4001 { name: s, id: u, email: o<s> }
+        choice Shape = 0x4002 { 0 Circle(u), 2 Rect(p<u u>), 3 Dot(p<>) }
+    ").unwrap();
+
+    let reports = compare_registries(&old, &new);
+    let (user, shape) = (&reports[0].1, &reports[1].1);
+    assert_eq!(user.changes, [
+        Change::FieldMoved { name: "name".into(), old_index: 1, new_index: 0 },
+        Change::FieldTypeChanged { index: 0, old: Type::Uint, new: Type::String },
+        Change::FieldMoved { name: "id".into(), old_index: 0, new_index: 1 },
+        Change::FieldTypeChanged { index: 1, old: Type::String, new: Type::Uint },
+        Change::FieldAppended { index: 2, name: "email".into(), has_default: false },
+    ]);
+    assert!(!user.old_reads_new() && !user.new_reads_old());
+    assert_eq!(shape.changes, [
+        Change::VariantIdChanged { name: "Rect".into(), old: 1, new: 2 },
+        Change::VariantAdded { id: 3, name: "Dot".into() },
+    ]);
+    assert!(!shape.new_reads_old());
+
+    // appended fields are read by old readers when decoding tolerantly, and only then
+    use crate::tolerant::Tolerance;
+    let old = Registry::parse("struct Item = 0x4003 { id: u }").unwrap();
+    let new = Registry::parse("struct Item = 0x4003 { id: u, note: s }").unwrap();
+    let reports = compare_registries(&old, &new);
+    assert_eq!(reports[0].1.changes, [Change::FieldAppended { index: 1, name: "note".into(), has_default: false }]);
+    assert!(reports[0].1.old_reads_new() && !reports[0].1.new_reads_old());
+    let buf = Value::<&'static [u8]>::Struct(TypeId::Std(0x4003), seq![], seq![Value::Uint(1), Value::String(s("a"))]).encode::<VecOutput>();
+    let mut tolerance = Tolerance { registry: &old, keep_unknown_fields: false };
+    assert!(Value::decode_tolerant::<SliceInput>(&buf, &tolerance).is_err());
+    tolerance.keep_unknown_fields = true;
+    assert_eq!(Value::decode_tolerant::<SliceInput>(&buf, &tolerance).unwrap(), Value::decode::<SliceInput>(&buf).unwrap());
+}
+
+#[test]
+fn tolerant() {
+    use crate::{schema::Registry, tolerant::Tolerance};
+
+    let registry = Registry::parse("
+        struct Item = 0x5001 { id: u, count: i = 1, note: s = \"-\", tags: l<s> = default, extra: o<u> }
+    ").unwrap();
+    let item = |values: Box<[Value<&'static [u8]>]>| Value::Struct(TypeId::Std(0x5001), seq![], values);
+    let old = item(seq![Value::Uint(7)]).encode::<VecOutput>();
+    let newer = item(seq![
+        Value::Uint(7),
+        Value::Int(2),
+        Value::String(s("")),
+        Value::List(Type::String, seq![]),
+        Value::Option(Type::Uint, None),
+        Value::Bool(true),
+    ]).encode::<VecOutput>();
+
+    let mut tolerance = Tolerance { registry: &registry, keep_unknown_fields: false };
+    assert_eq!(
+        Value::decode_tolerant::<SliceInput>(&old, &tolerance).unwrap_err().err,
+        Error::MissingFieldDefault { type_id: TypeId::Std(0x5001), index: 4 },
+    );
+    assert_eq!(
+        Value::decode_tolerant::<SliceInput>(&newer, &tolerance).unwrap_err().err,
+        Error::UnknownFields { type_id: TypeId::Std(0x5001), len: 6, exp_len: 5 },
+    );
+    tolerance.keep_unknown_fields = true;
+    assert_eq!(Value::decode_tolerant::<SliceInput>(&newer, &tolerance).unwrap(), Value::decode::<SliceInput>(&newer).unwrap());
+
+    let registry = Registry::parse("
+        struct Item = 0x5001 { id: u, count: i = 1, note: s = \"-\", tags: l<s> = default }
+    ").unwrap();
+    let tolerance = Tolerance { registry: &registry, keep_unknown_fields: false };
+    assert_eq!(Value::decode_tolerant::<SliceInput>(&old, &tolerance).unwrap(), item(seq![
+        Value::Uint(7),
+        Value::Int(1),
+        Value::String(s("-")),
+        Value::List(Type::String, seq![]),
+    ]));
+
+    // a registered struct without fields is not an unknown one
+    let registry = Registry::parse("struct Empty = 0x5002 {}").unwrap();
+    let extra = Value::<&'static [u8]>::Struct(TypeId::Std(0x5002), seq![], seq![Value::Uint(1)]).encode::<VecOutput>();
+    let mut tolerance = Tolerance { registry: &registry, keep_unknown_fields: false };
+    assert_eq!(
+        Value::decode_tolerant::<SliceInput>(&extra, &tolerance).unwrap_err().err,
+        Error::UnknownFields { type_id: TypeId::Std(0x5002), len: 1, exp_len: 0 },
+    );
+    tolerance.keep_unknown_fields = true;
+    assert_eq!(Value::decode_tolerant::<SliceInput>(&extra, &tolerance).unwrap(), Value::decode::<SliceInput>(&extra).unwrap());
+}
+
+#[test]
+fn migrate() {
+    use crate::migrate::*;
+
+    let user = |values: Box<[Value<&'static [u8]>]>| Value::Struct(TypeId::Std(0x6001), seq![], values);
+    let mut migrations = Migrations::new();
+    // v0: { name }, v1: { name, admin }, v2: { id, name, admin }
+    assert!(!migrations.insert(TypeId::Std(0x6001), 0, |value| {
+        let (type_id, generics, mut values) = struct_values(value)?;
+        values.push(Value::Bool(false));
+        Ok(Value::Struct(type_id, generics, values.into_boxed_slice()))
+    }));
+    assert!(!migrations.insert(TypeId::Std(0x6001), 1, |value| {
+        let (type_id, generics, mut values) = struct_values(value)?;
+        values.insert(0, Value::Uint(0));
+        Ok(Value::Struct(type_id, generics, values.into_boxed_slice()))
+    }));
+
+    let archived = Value::List(Type::Struct(TypeId::Std(0x6001), seq![]), seq![
+        user(seq![Value::String(s("a"))]),
+        user(seq![Value::String(s("b"))]),
+    ]);
+    let expected = Value::List(Type::Struct(TypeId::Std(0x6001), seq![]), seq![
+        user(seq![Value::Uint(0), Value::String(s("a")), Value::Bool(false)]),
+        user(seq![Value::Uint(0), Value::String(s("b")), Value::Bool(false)]),
+    ]);
+    assert_eq!(migrations.migrate(archived.clone(), 0).unwrap(), expected);
+    assert_eq!(migrations.migrate(expected.clone(), 2).unwrap(), expected);
+    assert_eq!(
+        migrations.migrate(user(seq![Value::String(s("a")), Value::Bool(true)]), 1).unwrap(),
+        user(seq![Value::Uint(0), Value::String(s("a")), Value::Bool(true)]),
+    );
+}
+
+#[test]
+fn infer() {
+    let v = |values: Box<[Value<&'static [u8]>]>| Value::Tuple(values);
+    let samples = [
+        v(seq![Value::Uint(1), Value::String(s("a")), Value::Option(Type::Int, None)]),
+        v(seq![Value::Uint(2), Value::Int(-1), Value::Int(3), Value::List(Type::Unknown, seq![])]),
+        v(seq![Value::Uint(3), Value::String(s("b")), Value::Option(Type::Int, None), Value::List(Type::String, seq![])]),
+        v(seq![Value::Uint(4)]),
+    ];
+    assert_eq!(Type::infer_from(&samples), Type::Tuple(seq![
+        Type::Uint,
+        Type::Option(Box::new(Type::Unknown)),
+        Type::Option(Box::new(Type::Int)),
+        Type::Option(Box::new(Type::List(Box::new(Type::String)))),
+    ]));
+    assert_eq!(Type::infer_from::<&'static [u8]>(&[]), Type::Unknown);
+    assert_eq!(Type::infer_from(&[Value::<&'static [u8]>::Uint(1), Value::Bool(true), Value::Uint(2)]), Type::Unknown);
+}
+
+#[test]
+fn default_for() {
+    use crate::schema::Registry;
+
+    assert_eq!(
+        Value::<&'static [u8]>::default_for(&Type::Tuple(seq![Type::Uint, Type::String, Type::Option(Box::new(Type::Int)), Type::List(Box::new(Type::Bool)), Type::Bytes])),
+        Some(Value::Tuple(seq![Value::Uint(0), Value::String(seq![]), Value::Option(Type::Int, None), Value::List(Type::Bool, seq![]), Value::Bytes(b(""))])),
+    );
+    assert_eq!(Value::<&'static [u8]>::default_for(&Type::Struct(TypeId::Std(0x7001), seq![])), None);
+
+    let registry = Registry::parse("
+        struct Form = 0x7001 <T> { name: s = \"untitled\", size: Size, shape: Shape<T>, extra: T }
+        alias Size = 0x7002 u
+        choice Shape = 0x7003 <T> { 1 Dot(p<>), 0 Other(T) }
+        struct Node = 0x7004 { next: Node }
+        struct Wrap = 0x7005 <T> { inner: T }
+        struct Grow = 0x7006 <T> { next: Grow<l<T>> }
+    ").unwrap();
+    let size = Value::Alias(TypeId::Std(0x7002), seq![], Box::new(Value::Uint(0)));
+    assert_eq!(
+        registry.default_value::<&'static [u8]>(&Type::Struct(TypeId::Std(0x7001), seq![Type::Int])),
+        Some(Value::Struct(TypeId::Std(0x7001), seq![Type::Int], seq![
+            Value::String(s("untitled")),
+            size,
+            Value::Choice(TypeId::Std(0x7003), seq![Type::Int], 1, Box::new(Value::Tuple(seq![]))),
+            Value::Int(0),
+        ])),
+    );
+    assert_eq!(registry.default_value::<&'static [u8]>(&Type::Struct(TypeId::Std(0x7004), seq![])), None);
+    assert_eq!(registry.default_value::<&'static [u8]>(&Type::Struct(TypeId::Std(0x7006), seq![Type::Uint])), None);
+
+    // nesting a definition in itself is not containing itself
+    let wrap = |r#type: Type| Type::Struct(TypeId::Std(0x7005), seq![r#type]);
+    assert_eq!(
+        registry.default_value::<&'static [u8]>(&wrap(wrap(Type::Uint))),
+        Some(Value::Struct(TypeId::Std(0x7005), seq![wrap(Type::Uint)], seq![
+            Value::Struct(TypeId::Std(0x7005), seq![Type::Uint], seq![Value::Uint(0)]),
+        ])),
+    );
+}
+
+#[test]
+fn well_known() {
+    use core::{time::Duration, net::{IpAddr, Ipv6Addr}};
+
+    fn text(v: &Value<Vec<u8>>) -> String {
+        String::from_utf8(v.encode_text::<VecOutput>()).unwrap()
+    }
+
+    let x = Value::<Vec<u8>>::f64(-0.5);
+    assert_eq!(x.as_f64(), Some(-0.5));
+    assert_eq!(x.as_f32(), None);
+    assert_eq!(text(&x), r#"@f64"-0.5""#);
+
+    let ts = Value::<Vec<u8>>::timestamp(1_792_281_600_000_000_000);
+    assert_eq!(text(&ts), r#"@timestamp"2026-10-18T00:00:00Z""#);
+    assert_eq!(text(&Value::timestamp(-1_500_000_000)), r#"@timestamp"1969-12-31T23:59:58.5Z""#);
+    assert_eq!(text(&Value::duration(Duration::new(90, 1000))), r#"@duration"90.000001s""#);
+
+    let uuid = Value::<Vec<u8>>::uuid(hex!("67e5504410b1426f9247bb680e5fe0c8"));
+    assert_eq!(text(&uuid), r#"@uuid"67e55044-10b1-426f-9247-bb680e5fe0c8""#);
+
+    let decimal = Value::<Vec<u8>>::decimal(-1200, 4);
+    assert_eq!(decimal.as_decimal(), Some((-12, 2)));
+    assert_eq!(text(&decimal), r#"@decimal"-0.12""#);
+
+    let addr = IpAddr::V6(Ipv6Addr::LOCALHOST);
+    let ip = Value::<Vec<u8>>::ip_addr(addr);
+    assert_eq!(ip.as_ip_addr(), Some(addr));
+    assert_eq!(text(&ip), r#"@ip_addr"::1""#);
+
+    // not canonical, rendered as a plain alias
+    let ip = Value::Alias(well_known::IP_ADDR, seq![], Box::new(Value::Bytes(vec![1, 2, 3])));
+    assert_eq!(ip.as_ip_addr(), None);
+    assert_eq!(text(&ip), r#"A(D(U'y' U0x14) G() B"010203")"#);
+
+    let registry = crate::schema::Registry::well_known();
+    registry.check(&ts).unwrap();
+    registry.check(&decimal).unwrap();
+}
+
+#[test]
+fn map() {
+    let m = |entries: Vec<(Value<&'static [u8]>, Value<&'static [u8]>)>| Value::map(Type::String, Type::Uint, entries);
+    let v = m(vec![
+        (Value::String(s("b")), Value::Uint(2)),
+        (Value::String(s("a")), Value::Uint(1)),
+        (Value::String(s("b")), Value::Uint(3)),
+    ]);
+    assert_eq!(v, Value::Map(Type::String, Type::Uint, seq![
+        (Value::String(s("a")), Value::Uint(1)),
+        (Value::String(s("b")), Value::Uint(3)),
+    ]));
+    let buf = v.encode::<VecOutput>();
+    assert_eq!(buf, expb!("4b 02  46 01  4d 02  53 01 61  55 01  53 01 62  55 03"));
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+    assert_eq!(
+        String::from_utf8(v.encode_text::<VecOutput>()).unwrap(),
+        r#"K(F1 M(S"a" U1 S"b" U3))"#,
+    );
+
+    let empty = m(vec![]);
+    let buf = empty.encode::<VecOutput>();
+    assert_eq!(buf, expb!("4b 02  46 00  47 02  54 02 55 73 50 00  54 02 55 75 50 00"));
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), empty);
+
+    let registry = crate::schema::Registry::parse("struct Scores = 0x8001 { scores: k<s u> }").unwrap();
+    registry.check(&Value::Struct(TypeId::Std(0x8001), seq![], seq![v])).unwrap();
+
+    let err = |buf: &[u8]| Value::decode::<SliceInput>(buf).unwrap_err().err;
+    assert_eq!(err(expb!("4b 02  46 01  4d 02  53 01 61  55 01  53 01 61  55 03")), Error::DuplicateMapKey);
+    assert_eq!(err(expb!("4b 02  46 01  4d 02  53 01 62  55 01  53 01 61  55 03")), Error::UnorderedMapKeys);
+
+    // keys are ordered with the item types the reader unifies, not the ones they carry
+    let list = |r#type: Type, items: Box<[Value<&'static [u8]>]>| Value::List(r#type, items);
+    let nested = |r#type: Type, item_type: Type| list(Type::List(Box::new(r#type)), seq![list(item_type, seq![])]);
+    let v = Value::Map(Type::Unknown, Type::Uint, seq![
+        (nested(Type::String, Type::Unknown), Value::Uint(1)),
+        (nested(Type::Unknown, Type::String), Value::Uint(2)),
+    ]);
+    let buf = v.encode::<VecOutput>();
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), Value::Map(
+        Type::List(Box::new(Type::List(Box::new(Type::String)))),
+        Type::Uint,
+        seq![
+            (nested(Type::Unknown, Type::Unknown), Value::Uint(1)),
+            (nested(Type::String, Type::String), Value::Uint(2)),
+        ],
+    ));
+    let key = |r#type: Type| list(r#type, seq![Value::Uint(1)]);
+    assert_eq!(
+        Value::map(Type::Unknown, Type::Uint, [(key(Type::String), Value::Uint(1)), (key(Type::Uint), Value::Uint(2))]),
+        Value::Map(Type::Unknown, Type::Uint, seq![(key(Type::Uint), Value::Uint(2))]),
+    );
+}
+
+#[test]
+fn fixed_width() {
+    use crate::convert::{FromValue, IntoValue, ConvertError};
+
+    let v = Value::<Vec<u8>>::u16(500);
+    assert_eq!(v, Value::Alias(well_known::U16, seq![], Box::new(Value::Uint(500))));
+    assert_eq!(v.as_u16(), Some(500));
+    assert_eq!(v.as_u8(), None);
+    assert_eq!(String::from_utf8(v.encode_text::<VecOutput>()).unwrap(), r#"@u16"500""#);
+    assert_eq!(u16::from_value(v.clone()), Ok(500));
+    assert_eq!(IntoValue::<Vec<u8>>::into_value(500u16), v);
+
+    let i = Value::<Vec<u8>>::i8(-3);
+    let buf = i.encode::<VecOutput>();
+    assert_eq!(Value::decode::<SliceInput>(&*buf).unwrap(), Value::Alias(well_known::I8, seq![], Box::new(Value::Int(-3))));
+
+    let wide = |type_id: TypeId, value: Value<&'static [u8]>| Value::Alias(type_id, seq![], Box::new(value));
+    let buf = wide(well_known::U16, Value::Uint(70000)).encode::<VecOutput>();
+    assert_eq!(Value::decode::<SliceInput>(&*buf).unwrap_err().err, Error::ULEB128LongerThanTargetType(70000, "u16"));
+    let buf = wide(well_known::I8, Value::Int(-129)).encode::<VecOutput>();
+    assert_eq!(Value::decode::<SliceInput>(&*buf).unwrap_err().err, Error::SLEB128LongerThanTargetType(-129, "i8"));
+    assert_eq!(u16::from_value(wide(well_known::U16, Value::Uint(70000))), Err(ConvertError::OutOfRange(well_known::U16)));
+
+    // generic payloads of the same ids are not width-checked
+    let buf = Value::Alias(well_known::U8, seq![Type::Uint], Box::new(Value::Uint(300))).encode::<VecOutput>();
+    let _ = Value::decode::<SliceInput>(&*buf).unwrap();
+
+    let registry = crate::schema::Registry::well_known();
+    registry.check(&i).unwrap();
+    assert_eq!(registry.check(&wide(well_known::U8, Value::Uint(300))), Err(crate::check::CheckError::OutOfRange(well_known::U8)));
+    assert_eq!(registry.check(&Value::Tuple(seq![wide(well_known::I8, Value::Int(-129))])), Err(crate::check::CheckError::OutOfRange(well_known::I8)));
+}
+
+#[test]
+fn big_int() {
+    use crate::convert::{BigUint, BigInt};
+
+    // same LEB128 as the fixed-size integers where they fit
+    for n in [0, 1, 63, 64, 127, 128, 300, u64::MAX as u128, u128::MAX] {
+        let v = Value::<&'static [u8]>::BigUint(BigUint::from(n).0);
+        let buf = v.encode::<VecOutput>();
+        assert_eq!(buf[1..], Value::<&'static [u8]>::Uint(n).encode::<VecOutput>()[1..]);
+        assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+    }
+    for n in [0, 1, -1, 63, 64, -64, -65, -129, i128::MIN, i128::MAX] {
+        let v = Value::<&'static [u8]>::BigInt(BigInt::from(n).0);
+        let buf = v.encode::<VecOutput>();
+        assert_eq!(buf[1..], Value::<&'static [u8]>::Int(n).encode::<VecOutput>()[1..]);
+        assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+    }
+
+    let max = Value::<&'static [u8]>::big_uint(&[0xff; 32]);
+    let buf = max.encode::<VecOutput>();
+    assert_eq!(buf.len(), 1 + 37);
+    assert_eq!(buf.last(), Some(&0x0f));
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), max);
+
+    let mut le = [0; 33];
+    le[32] = 0x80;
+    let min = Value::<&'static [u8]>::big_int(&le);
+    let buf = min.encode::<VecOutput>();
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), min);
+
+    assert_eq!(Value::<&'static [u8]>::big_uint(&[1, 0, 0]), Value::BigUint(seq![1]));
+    assert_eq!(Value::<&'static [u8]>::big_int(&[0x80, 0xff, 0xff]), Value::BigInt(seq![0x80]));
+    assert_eq!(Value::<&'static [u8]>::big_int(&[0x80, 0]), Value::BigInt(seq![0x80, 0]));
+    assert_eq!(Value::<&'static [u8]>::big_int(&[0, 0]), Value::BigInt(seq![]));
+
+    // ordered numerically
+    let uints = [0, 1, 127, 128, 255, 256, u64::MAX as u128, u128::MAX];
+    for a in uints {
+        for b in uints {
+            let v = |n: u128| Value::<&'static [u8]>::BigUint(BigUint::from(n).0);
+            assert_eq!(v(a).canonical_cmp(&v(b)), a.cmp(&b), "{a} {b}");
+        }
+    }
+    let ints = [i128::MIN, -257, -256, -129, -128, -1, 0, 1, 127, 128, 255, 256, i128::MAX];
+    for a in ints {
+        for b in ints {
+            let v = |n: i128| Value::<&'static [u8]>::BigInt(BigInt::from(n).0);
+            assert_eq!(v(a).canonical_cmp(&v(b)), a.cmp(&b), "{a} {b}");
+        }
+    }
+    assert!(max.canonical_cmp(&Value::BigUint(BigUint::from(u128::MAX).0)).is_gt());
+    assert!(min.canonical_cmp(&Value::BigInt(BigInt::from(i128::MIN).0)).is_lt());
+
+    let text = |v: Value<&'static [u8]>| String::from_utf8(v.encode_text::<VecOutput>()).unwrap();
+    assert_eq!(text(Value::big_uint(&[])), "V0x00");
+    assert_eq!(text(Value::big_uint(&[0x34, 0x12])), "V0x1234");
+    assert_eq!(text(Value::big_int(&(-256i128).to_le_bytes())), "W-0x0100");
+
+    let max_lens = MaxLens { big_int: 2, ..DEFAULT_MAX_LENS };
+    let buf = Value::<&'static [u8]>::big_uint(&[0xff, 0xff]).encode::<VecOutput>();
+    assert_eq!(
+        Value::decode_with_max_lens::<SliceInput>(&*buf, max_lens).unwrap_err().err,
+        Error::MaxLen(MaxLenType::BigInt, MaxLenExceedValue::Size(3)),
+    );
+    assert_eq!(Value::decode::<SliceInput>(expb!("56 80 00")).unwrap_err().err, Error::LEB128TrailingEmptyBytes);
+    assert_eq!(Value::decode::<SliceInput>(expb!("57 ff 7f")).unwrap_err().err, Error::LEB128TrailingEmptyBytes);
+}
+
+#[test]
+fn utf8() {
+    let v = Value::<&'static [u8]>::utf8("你好".as_bytes()).unwrap();
+    let buf = v.encode::<VecOutput>();
+    assert_eq!(buf, expb!("51 06 e4bda0 e5a5bd"));
+    let decoded = Value::decode::<SliceInput>(&*buf).unwrap();
+    assert_eq!(decoded.as_str(), Some("你好"));
+    // borrowed from the input
+    let Value::Utf8(bytes) = decoded else { unreachable!() };
+    assert_eq!(bytes.as_ptr(), buf[2..].as_ptr());
+    assert_eq!(String::from_utf8(v.encode_text::<VecOutput>()).unwrap(), r#"Q"你好""#);
+
+    assert!(Value::<&'static [u8]>::utf8(&b"\xc3\x28"[..]).is_err());
+    assert!(matches!(Value::decode::<SliceInput>(expb!("51 02 c3 28")).unwrap_err().err, Error::Utf8(_)));
+    let registry = crate::schema::Registry::parse("struct Note = 0x9001 { text: q }").unwrap();
+    registry.check(&Value::Struct(TypeId::Std(0x9001), seq![], seq![v])).unwrap();
+}
+
+#[test]
+fn uints_encodings() {
+    fn case(uints: Box<[u128]>, exp: &'static [u8]) {
+        let v = Value::<&'static [u8]>::Uints(uints);
+        let buf = v.encode::<VecOutput>();
+        assert_eq!(&buf, exp);
+        assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+    }
+
+    case(seq![], expb!("4e 00"));
+    case(seq![1000, 1001, 1003, 1010], expb!("48 04 e807 01 02 07"));
+    case(seq![1, 0, 3, 2, 1, 1, 0, 3], expb!("4a 08 02 b1c5"));
+    case(seq![0, 0, 0], expb!("4a 03 01 00"));
+    // ties keep the plain encoding
+    case(seq![1, 2], expb!("4e 02 01 02"));
+    case(seq![u128::MAX, 0], expb!("4e 02 ffffffffffffffffffffffffffffffffffff03 00"));
+
+    let err = |buf: &'static [u8]| Value::decode::<SliceInput>(buf).unwrap_err().err;
+    assert_eq!(err(expb!("48 02 01 ffffffffffffffffffffffffffffffffffff03")), Error::UintsDeltaOverflow);
+    assert_eq!(err(expb!("4a 01 81 01")), Error::UintsBitWidth(129));
+    assert_eq!(err(expb!("4a 01 02 05")), Error::UintsPackedPadding);
+    assert_eq!(err(expb!("4a ffffffff0f 00")), Error::UintsBitWidth(0));
+}
+
+#[test]
+fn type_table() {
+    let v = Value::<&'static [u8]>::Tuple(seq![Value::Type(Type::Uint), Value::Type(Type::Uint)]);
+    let buf = v.encode_with_type_table::<VecOutput>();
+    assert_eq!(buf, expb!("58 01  54 02 55 75 50 00  50 02  59 00  59 00"));
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+
+    let point = |x: u128| Value::Struct(TypeId::Std(0x1001), seq![Type::Uint], seq![
+        Value::Uint(x),
+        Value::Option(Type::Uint, None),
+    ]);
+    let v = Value::<&'static [u8]>::List(Type::Unknown, (0..100).map(point).collect());
+    let buf = v.encode_with_type_table::<VecOutput>();
+    assert!(buf.len() * 3 < v.encode::<VecOutput>().len() * 2);
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap(), v);
+
+    // nothing repeated, no table
+    let v = case_1();
+    assert_eq!(v.encode_with_type_table::<VecOutput>(), v.encode::<VecOutput>());
+
+    let err = |buf: &'static [u8]| Value::decode::<SliceInput>(buf).unwrap_err().err;
+    assert_eq!(err(expb!("59 00")), Error::UnknownTypeRef(0));
+    assert_eq!(err(expb!("50 01  58 00  55 01")), Error::TypeTableNotOnTop);
+    assert_eq!(
+        err(expb!("58 01  54 02 55 75 50 00  45 02 59 00 55 01")),
+        Error::ExpectedTypeMismatch { tag: Tag::Type, exp_tag: Tag::TypeId },
+    );
+}
+
+#[test]
+fn shared() {
+    let hello = || Value::String(s("hello, world"));
+    let v = Value::<&'static [u8]>::Tuple(seq![hello(), hello(), hello(), Value::Uint(1), Value::Uint(1)]);
+    let buf = v.encode_shared::<VecOutput>();
+    assert_eq!(buf, expb!("
+        50 05
+            5a 02 46 01 53 0c 68656c6c6f2c20776f726c64
+            5a 02 46 00 55 00
+            5a 02 46 00 55 00
+            55 01
+            55 01
+    "));
+    assert_eq!(Value::decode_shared::<SliceInput>(&buf).unwrap(), v);
+    assert_eq!(Value::decode::<SliceInput>(&buf).unwrap_err().err, Error::UnexpectedShared);
+
+    // values in a shared one are written once with it, and not shared themselves
+    let pair = || Value::Tuple(seq![hello(), Value::String(s("goodbye, world"))]);
+    let nested = Value::<&'static [u8]>::Tuple(seq![pair(), pair()]);
+    let nested_buf = nested.encode_shared::<VecOutput>();
+    assert_eq!(nested_buf, expb!("
+        50 02
+            5a 02 46 01 50 02 53 0c 68656c6c6f2c20776f726c64 53 0e 676f6f646279652c20776f726c64
+            5a 02 46 00 55 00
+    "));
+    assert_eq!(Value::decode_shared::<SliceInput>(&nested_buf).unwrap(), nested);
+
+    // payloads of fixed-width integers stay bare integers, as the reader expects them
+    let max = || Value::Uint(u64::MAX.into());
+    let v = Value::<&'static [u8]>::Tuple(seq![max(), Value::u64(u64::MAX), max()]);
+    let fixed_buf = v.encode_shared::<VecOutput>();
+    assert_eq!(Value::decode_shared::<SliceInput>(&fixed_buf).unwrap(), v);
+    let v = Value::<&'static [u8]>::Tuple(seq![max(), Value::u64(u64::MAX)]);
+    assert_eq!(v.encode_shared::<VecOutput>(), v.encode::<VecOutput>());
+
+    // nothing worth sharing
+    let v = case_1();
+    assert_eq!(v.encode_shared::<VecOutput>(), v.encode::<VecOutput>());
+
+    let max_lens = MaxLens { expansion: 20, ..DEFAULT_MAX_LENS };
+    assert_eq!(
+        Value::decode_shared_with_max_lens::<SliceInput>(&*buf, max_lens).unwrap_err().err,
+        Error::MaxLen(MaxLenType::Expansion, MaxLenExceedValue::Size(26)),
+    );
+    assert_eq!(Value::decode_shared::<SliceInput>(expb!("5a 02 46 00 55 00")).unwrap_err().err, Error::UnknownSharedRef(0));
+}
+
+#[test]
+fn schema_encoding() {
+    use crate::{schema::Registry, check::CheckError};
+
+    let registry = Registry::parse("
+        struct Point = 0xa001 <T> { x: T, tags: l<s>, color: Color }
+        enum Color = 0xa002 { 0 Red, 1 Green }
+        choice Shape = 0xa003 { 0 Circle(u), 1 Rect(p<u u>) }
+    ").unwrap();
+    let roundtrip = |v: Value<&'static [u8]>, r#type: Type, exp: &[u8]| {
+        let buf = v.encode_with_schema::<VecOutput>(&r#type, &registry).unwrap();
+        assert_eq!(buf, exp);
+        assert_eq!(Value::decode_with_schema::<SliceInput>(&buf, &r#type, &registry).unwrap(), v);
+    };
+
+    roundtrip(
+        Value::Struct(TypeId::Std(0xa001), seq![Type::Uint], seq![
+            Value::Uint(5),
+            Value::List(Type::String, seq![Value::String(s("a"))]),
+            Value::Enum(TypeId::Std(0xa002), 1),
+        ]),
+        Type::Struct(TypeId::Std(0xa001), seq![Type::Uint]),
+        expb!("05  01 01 61  01"),
+    );
+    let shape = Type::Choice(TypeId::Std(0xa003), seq![]);
+    roundtrip(
+        Value::Choice(TypeId::Std(0xa003), seq![], 1, Box::new(Value::Tuple(seq![Value::Uint(2), Value::Uint(3)]))),
+        shape.clone(),
+        expb!("01  02 03"),
+    );
+    roundtrip(
+        Value::Tuple(seq![Value::Option(Type::Bool, None), Value::Uint(1)]),
+        Type::Tuple(seq![Type::Option(Box::new(Type::Bool)), Type::Unknown]),
+        expb!("00  55 01"),
+    );
+
+    assert_eq!(
+        Value::<&'static [u8]>::Int(1).encode_with_schema::<VecOutput>(&Type::Uint, &registry).unwrap_err(),
+        CheckError::TypeMismatch { r#type: Type::Int, exp_type: Type::Uint },
+    );
+    let err = |buf: &'static [u8], r#type: &Type| Value::decode_with_schema::<SliceInput>(buf, r#type, &registry).unwrap_err().err;
+    assert_eq!(
+        err(expb!("05"), &shape),
+        Error::Check(CheckError::UnknownVariant { type_id: TypeId::Std(0xa003), variant_id: 5 }),
+    );
+    assert_eq!(err(expb!("01"), &Type::Param(0)), Error::ParamInSchema(0));
+
+    // fixed-width integers are range checked both ways
+    let registry = Registry::well_known();
+    let r#type = Type::Alias(well_known::U8, seq![]);
+    let v = Value::<&'static [u8]>::u8(200);
+    let buf = v.encode_with_schema::<VecOutput>(&r#type, &registry).unwrap();
+    assert_eq!(buf, expb!("c8 01"));
+    assert_eq!(Value::decode_with_schema::<SliceInput>(&buf, &r#type, &registry).unwrap(), v);
+    let v = Value::<&'static [u8]>::Alias(well_known::U8, seq![], Box::new(Value::Uint(300)));
+    assert_eq!(v.encode_with_schema::<VecOutput>(&r#type, &registry), Err(CheckError::OutOfRange(well_known::U8)));
+    assert!(matches!(
+        Value::decode_with_schema::<SliceInput>(expb!("ac 02"), &r#type, &registry).unwrap_err().err,
+        Error::ULEB128LongerThanTargetType(300, _),
+    ));
+}
+
+#[test]
+fn value_ref() {
+    use crate::value_ref::ValueRef;
+
+    let buf = case_1().encode::<VecOutput>();
+    let v = ValueRef::new(&buf).unwrap();
+    assert_eq!(v.to_value().unwrap(), case_1());
+    let mut items = v.list_iter().unwrap();
+    assert_eq!(items.len(), 2);
+    let second = items.nth(1).unwrap();
+    assert_eq!(second.get(0).unwrap().unwrap().as_uint().unwrap(), 999999);
+    let strings = second.get(1).unwrap().unwrap().list_iter().unwrap();
+    assert_eq!(strings.map(|item| item.to_value().unwrap()).collect::<Vec<_>>(), [
+        Value::String(s("how are you")),
+        Value::String(s("fine")),
+        Value::String(s("thanks")),
+    ]);
+    assert_eq!(second.get(2).unwrap(), None);
+    assert_eq!(v.as_uint().unwrap_err(), Error::ExpectedTypeMismatch { tag: Tag::List, exp_tag: Tag::Uint });
+
+    let note = Value::<&'static [u8]>::Struct(TypeId::Std(0x9001), seq![], seq![
+        Value::Utf8(&b"hi"[..]),
+        Value::Option(Type::Uint, None),
+    ]);
+    let buf = note.encode::<VecOutput>();
+    let v = ValueRef::new(&buf).unwrap();
+    let (type_id, mut fields) = v.as_struct().unwrap();
+    assert_eq!(type_id, TypeId::Std(0x9001));
+    assert_eq!(fields.next().unwrap().as_str().unwrap(), "hi");
+    assert_eq!(fields.next().unwrap().as_option().unwrap(), None);
+
+    // one value exactly
+    assert!(ValueRef::new(&buf[..buf.len() - 1]).is_err());
+    assert!(ValueRef::new(&[buf.as_slice(), &[0x55, 0x01][..]].concat()).is_err());
+}
+
+#[test]
+fn query_path() {
+    use crate::{schema::Registry, query::*};
+
+    let buf = case_1().encode::<VecOutput>();
+    let strings = |path| crate::query(&buf, path).unwrap().into_iter()
+        .map(|m| m.value.to_value().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(strings("/*/1/0"), [Value::String(s("hello")), Value::String(s("how are you"))]);
+    assert_eq!(strings("/1/0"), [Value::Uint(999999)]);
+    assert!(strings("/5/0").is_empty());
+    let m = &crate::query(&buf, "/0/0").unwrap()[0];
+    assert_eq!(&buf[m.range.clone()], m.value.encoded());
+    assert_eq!(Value::decode::<SliceInput>(m.value.encoded()).unwrap(), Value::Uint(123));
+
+    let registry = Registry::parse("
+        struct Order = 0xb001 { id: u, items: l<p<s u>>, note: o<q> }
+        choice Event = 0xb002 { 0 Placed(Order), 1 Cancelled(u) }
+    ").unwrap();
+    let order = Value::<&'static [u8]>::Struct(TypeId::Std(0xb001), seq![], seq![
+        Value::Uint(7),
+        Value::List(Type::Unknown, seq![
+            Value::Tuple(seq![Value::String(s("apple")), Value::Uint(3)]),
+            Value::Tuple(seq![Value::String(s("pear")), Value::Uint(1)]),
+        ]),
+        Value::Option(Type::Utf8, Some(Box::new(Value::Utf8(&b"fast"[..])))),
+    ]);
+    let event = Value::Choice(TypeId::Std(0xb002), seq![], 0, Box::new(order));
+    let buf = event.encode::<VecOutput>();
+    let values = |path| query_with_registry(&buf, path, Some(&registry)).unwrap().into_iter()
+        .map(|m| m.value.to_value().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(values("/0/items/*/1"), [Value::Uint(3), Value::Uint(1)]);
+    assert_eq!(values("/0/note/0"), [Value::Utf8(&b"fast"[..])]);
+    assert!(values("/1/id").is_empty());
+    assert_eq!(crate::query(&buf, "/0/items"), Err(QueryError::FieldName("items".into())));
+    assert_eq!(crate::query(&buf, "0//"), Err(QueryError::Path("0//".into())));
+}
+
+#[test]
+fn list_index() {
+    use crate::value_ref::{ValueRef, ListIndex};
+
+    let record = |id: u128| Value::Tuple(seq![Value::Uint(id * 10), Value::String(s("x").repeat(id as usize % 7).into())]);
+    let v = Value::<&'static [u8]>::List(Type::Unknown, (0..100).map(record).collect());
+    let buf = v.encode::<VecOutput>();
+    let list = ValueRef::new(&buf).unwrap();
+    let index = ListIndex::build(&list).unwrap();
+    assert_eq!(index.len(), 100);
+    assert_eq!(index.get(&list, 57).unwrap().unwrap().to_value().unwrap(), record(57));
+    assert_eq!(index.get(&list, 100).unwrap(), None);
+
+    let index = ListIndex::decode(&index.encode::<VecOutput>()).unwrap();
+    let key = |item: &ValueRef| item.get(0).unwrap().unwrap().as_uint().unwrap();
+    assert_eq!(index.binary_search_by(&list, |item| key(item).cmp(&420)).unwrap(), Ok(42));
+    assert_eq!(index.binary_search_by(&list, |item| key(item).cmp(&425)).unwrap(), Err(43));
+
+    let other = case_1().encode::<VecOutput>();
+    assert_eq!(index.get(&ValueRef::new(&other).unwrap(), 0), Err(Error::ListIndexMismatch));
+    assert_eq!(ListIndex::decode(&Value::<&'static [u8]>::Uints(seq![3, 2]).encode::<VecOutput>()), Err(Error::ListIndexMismatch));
+}
+
+#[test]
+fn key_encoding() {
+    let values: Vec<Value<&'static [u8]>> = vec![
+        Value::Uint(0),
+        Value::Uint(1),
+        Value::Uint(256),
+        Value::Uint(u128::MAX),
+        Value::Int(i128::MIN),
+        Value::Int(-1),
+        Value::Int(0),
+        Value::Int(300),
+        Value::Bool(false),
+        Value::Bool(true),
+        Value::big_uint(&[]),
+        Value::big_uint(&[0xFF]),
+        Value::big_uint(&[0, 1]),
+        Value::big_uint(&[0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]),
+        Value::big_int(&[0xFF, 0xFE]),
+        Value::big_int(&[0x7F, 0xFF]),
+        Value::big_int(&[0x80]),
+        Value::big_int(&[0xFF]),
+        Value::big_int(&[]),
+        Value::big_int(&[1]),
+        Value::big_int(&[0x80, 0]),
+        Value::Uints(seq![]),
+        Value::Uints(seq![1]),
+        Value::Uints(seq![1, 0]),
+        Value::Bytes(&b""[..]),
+        Value::Bytes(&b"\x00"[..]),
+        Value::Bytes(&b"\x00\x00"[..]),
+        Value::Bytes(&b"\x00\x01"[..]),
+        Value::Bytes(&b"\x01"[..]),
+        Value::String(s("ab")),
+        Value::String(s("b")),
+        Value::Utf8(&b"a"[..]),
+        Value::Tuple(seq![]),
+        Value::Tuple(seq![Value::Uint(1)]),
+        Value::Tuple(seq![Value::Uint(1), Value::Bool(false)]),
+        Value::Tuple(seq![Value::Int(0)]),
+        Value::List(Type::Uint, seq![]),
+        Value::List(Type::Uint, seq![Value::Uint(2)]),
+        Value::List(Type::List(Box::new(Type::Uint)), seq![]),
+        Value::Option(Type::Uint, None),
+        Value::Option(Type::Uint, Some(Box::new(Value::Uint(0)))),
+        Value::map(Type::String, Type::Uint, [(Value::String(s("a")), Value::Uint(1))]),
+        Value::Enum(TypeId::Std(1), 2),
+        Value::Enum(TypeId::ThirdParty(0, 1), 0),
+        Value::Struct(TypeId::Std(1), seq![Type::Uint], seq![Value::Uint(1)]),
+        Value::Type(Type::Tuple(seq![Type::Param(3)])),
+        Value::TypeId(TypeId::Anonymous),
+    ];
+    for a in &values {
+        for b in &values {
+            assert_eq!(
+                a.encode_key::<VecOutput>().cmp(&b.encode_key::<VecOutput>()),
+                a.canonical_cmp(b),
+                "{a:?} {b:?}",
+            );
+        }
+    }
+    for pair in values.windows(2) {
+        assert!(pair[0].canonical_cmp(&pair[1]).is_lt(), "{:?} {:?}", pair[0], pair[1]);
+    }
+
+    // numerically, 255 < 256 and -1 < 0
+    let key = |v: Value<&'static [u8]>| v.encode_key::<VecOutput>();
+    assert!(key(Value::big_uint(&[0xFF])) < key(Value::big_uint(&[0, 1])));
+    assert!(key(Value::big_int(&[0xFF])) < key(Value::big_int(&[])));
+}
+
+#[test]
+fn canonical() {
+    for v in [case_1(), case_2()] {
+        let buf = v.encode::<VecOutput>();
+        assert!(Value::is_canonical::<SliceInput>(&buf));
+        assert_eq!(v.canonical_encode().unwrap(), buf);
+    }
+
+    let err = |buf: &[u8]| Value::decode_canonical::<SliceInput>(buf).unwrap_err().err;
+    let v = Value::<&'static [u8]>::Tuple(seq![Value::Type(Type::Uint), Value::Type(Type::Uint)]);
+    assert_eq!(err(&v.encode_with_type_table::<VecOutput>()), Error::NonCanonical);
+    let hello = || Value::String(s("hello, world"));
+    let v = Value::<&'static [u8]>::Tuple(seq![hello(), hello(), hello()]);
+    assert_eq!(err(&v.encode_shared::<VecOutput>()), Error::NonCanonical);
+
+    // the writer packs these
+    let plain = expb!("4e 08 01 01 01 01 01 01 01 01");
+    assert_eq!(Value::decode::<SliceInput>(plain).unwrap(), Value::Uints(Box::new([1; 8])));
+    assert_eq!(err(plain), Error::NonCanonical);
+    assert_eq!(err(expb!("4a 01 02 01")), Error::NonCanonical);
+
+    let v = Value::<&'static [u8]>::Map(Type::Uint, Type::Uint, seq![(Value::Uint(1), Value::Uint(1)), (Value::Uint(1), Value::Uint(2))]);
+    assert_eq!(v.canonical_encode(), Err(Error::DuplicateMapKey));
+}
+
+#[test]
+fn digest() {
+    use crate::digest::Digest;
+
+    #[derive(Default)]
+    struct Collect(Vec<u8>);
+
+    impl Digest for Collect {
+        type Output = Vec<u8>;
+
+        fn update(&mut self, bytes: &[u8]) {
+            self.0.extend_from_slice(bytes);
+        }
+
+        fn finalize(self) -> Vec<u8> {
+            self.0
+        }
+    }
+
+    // FNV-1a 64
+    struct Fnv(u64);
+
+    impl Default for Fnv {
+        fn default() -> Self {
+            Fnv(0xcbf29ce484222325)
+        }
+    }
+
+    impl Digest for Fnv {
+        type Output = u64;
+
+        fn update(&mut self, bytes: &[u8]) {
+            for byte in bytes {
+                self.0 = (self.0 ^ *byte as u64).wrapping_mul(0x100000001b3);
+            }
+        }
+
+        fn finalize(self) -> u64 {
+            self.0
+        }
+    }
+
+    // streamed, the same bytes as the canonical encoding
+    for v in [case_1(), case_2()] {
+        assert_eq!(v.digest::<Collect>().unwrap(), v.canonical_encode().unwrap());
+    }
+
+    // entries in any order have the canonical encoding
+    let entries = [(Value::Uint(2), Value::Bool(true)), (Value::Uint(1), Value::Bool(false))];
+    let unsorted = Value::<&'static [u8]>::Map(Type::Uint, Type::Bool, Box::new(entries.clone()));
+    let sorted = Value::map(Type::Uint, Type::Bool, entries);
+    assert_eq!(unsorted.digest::<Fnv>(), sorted.digest::<Fnv>());
+
+    // digests are stored, so these must never change, including the choice of `Uints` encodings
+    let golden: [(Value<&'static [u8]>, &[u8], u64); 5] = [
+        (Value::Uint(1), expb!("55 01"), 0x09316b07b5c3fea3),
+        (Value::Uints(seq![1, 2]), expb!("4e 02 01 02"), 0x1d0012c73a4cc12e),
+        (Value::Uints(seq![1000, 1001, 1003, 1010]), expb!("48 04 e807 01 02 07"), 0x05f552f575285028),
+        (Value::Uints(seq![1, 0, 3, 2, 1, 1, 0, 3]), expb!("4a 08 02 b1c5"), 0x7f2e62dea38756f5),
+        (unsorted, expb!("4b 02 46 01 4d 02 55 01 46 00 55 02 46 01"), 0xeae649aafbe4e310),
+    ];
+    for (v, buf, digest) in golden {
+        assert_eq!(v.canonical_encode().unwrap(), buf);
+        assert_eq!(v.digest::<Collect>().unwrap(), buf);
+        assert_eq!(v.digest::<Fnv>().unwrap(), digest);
+    }
+
+    // no canonical form, no digest
+    let v = Value::<&'static [u8]>::Map(Type::Uint, Type::Uint, seq![(Value::Uint(1), Value::Uint(1)), (Value::Uint(1), Value::Uint(2))]);
+    assert_eq!(v.digest::<Fnv>(), Err(Error::DuplicateMapKey));
+    assert_eq!(v.canonical_encode(), Err(Error::DuplicateMapKey));
+    // keys equal as read back, see `Value::map`
+    let list = |r#type| Value::List(r#type, seq![Value::List(Type::Unknown, seq![])]);
+    let v = Value::<&'static [u8]>::Map(
+        Type::List(Box::new(Type::Unknown)),
+        Type::Uint,
+        seq![(list(Type::Unknown), Value::Uint(1)), (list(Type::List(Box::new(Type::Uint))), Value::Uint(2))],
+    );
+    assert_eq!(v.digest::<Fnv>(), Err(Error::DuplicateMapKey));
+    assert_eq!(v.canonical_encode(), Err(Error::DuplicateMapKey));
+    let v = Value::<&'static [u8]>::List(Type::Unknown, seq![Value::Uint(1), Value::Bool(true)]);
+    let err = Error::ListItemTypeMismatch { r#type: Type::Uint, item_type: Type::Bool };
+    assert_eq!(v.digest::<Fnv>(), Err(err.clone()));
+    assert_eq!(v.canonical_encode(), Err(err));
+    let v = Value::<&'static [u8]>::Alias(well_known::U8, seq![], Box::new(Value::Uint(300)));
+    assert_eq!(v.digest::<Fnv>(), Err(Error::NonCanonical));
+    assert!(v.canonical_encode().is_err());
+}
+
+// the expected output, compiled as it is and compared ignoring whitespace
+#[cfg(feature = "codegen")]
+macro_rules! golden {
+    ($($code:tt)*) => {
+        #[allow(dead_code)]
+        mod generated {
+            $($code)*
+        }
+
+        const GOLDEN: &str = stringify!($($code)*);
+    };
+}
+
+#[cfg(feature = "codegen")]
+golden! {
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Node {
+        pub value: u128,
+        pub next: Option<Box<Node>>,
+        pub r#type: Vec<Node>,
+        pub children: std::collections::BTreeMap<Expr, Node>,
+    }
+    impl crate::convert::FromValue<Vec<u8>> for Node {
+        fn from_value(value: crate::Value<Vec<u8>>) -> Result<Self, crate::convert::ConvertError> {
+            let [f0, f1, f2, f3] = crate::convert::struct_fields::<_, 4>(value, &crate::TypeId::Std(0x7001))?;
+            Ok(Self {
+                value: crate::convert::FromValue::from_value(f0)?,
+                next: crate::convert::FromValue::from_value(f1)?,
+                r#type: crate::convert::FromValue::from_value(f2)?,
+                children: crate::convert::FromValue::from_value(f3)?,
+            })
+        }
+    }
+    impl crate::convert::IntoValue<Vec<u8>> for Node {
+        fn value_type() -> crate::Type { crate::Type::Struct(crate::TypeId::Std(0x7001), Box::new([])) }
+        fn into_value(self) -> crate::Value<Vec<u8>> {
+            crate::Value::Struct(crate::TypeId::Std(0x7001), Box::new([]), Box::new([
+                crate::convert::IntoValue::<Vec<u8>>::into_value(self.value),
+                crate::convert::IntoValue::<Vec<u8>>::into_value(self.next),
+                crate::convert::IntoValue::<Vec<u8>>::into_value(self.r#type),
+                crate::convert::IntoValue::<Vec<u8>>::into_value(self.children),
+            ]))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Tagged<T> {
+        pub id: u128,
+        pub _marker: core::marker::PhantomData<(T, )>,
+    }
+    impl<T: crate::convert::FromValue<Vec<u8>>> crate::convert::FromValue<Vec<u8>> for Tagged<T> {
+        fn from_value(value: crate::Value<Vec<u8>>) -> Result<Self, crate::convert::ConvertError> {
+            let [f0] = crate::convert::struct_fields::<_, 1>(value, &crate::TypeId::Std(0x7002))?;
+            Ok(Self {
+                id: crate::convert::FromValue::from_value(f0)?,
+                _marker: core::marker::PhantomData,
+            })
+        }
+    }
+    impl<T: crate::convert::IntoValue<Vec<u8>>> crate::convert::IntoValue<Vec<u8>> for Tagged<T> {
+        fn value_type() -> crate::Type { crate::Type::Struct(crate::TypeId::Std(0x7002), Box::new([T::value_type(), ])) }
+        fn into_value(self) -> crate::Value<Vec<u8>> {
+            crate::Value::Struct(crate::TypeId::Std(0x7002), Box::new([T::value_type(), ]), Box::new([
+                crate::convert::IntoValue::<Vec<u8>>::into_value(self.id),
+            ]))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    pub enum Expr {
+        Lit(i128),
+        Neg(Box<Expr>),
+    }
+    impl crate::convert::FromValue<Vec<u8>> for Expr {
+        fn from_value(value: crate::Value<Vec<u8>>) -> Result<Self, crate::convert::ConvertError> {
+            let (id, value) = crate::convert::choice_payload(value, &crate::TypeId::Std(0x7003))?;
+            match id {
+                0x0 => Ok(Self::Lit(crate::convert::FromValue::from_value(value)?)),
+                0x1 => Ok(Self::Neg(crate::convert::FromValue::from_value(value)?)),
+                id => Err(crate::convert::ConvertError::UnknownVariant(id)),
+            }
+        }
+    }
+    impl crate::convert::IntoValue<Vec<u8>> for Expr {
+        fn value_type() -> crate::Type { crate::Type::Choice(crate::TypeId::Std(0x7003), Box::new([])) }
+        fn into_value(self) -> crate::Value<Vec<u8>> {
+            let (id, value) = match self {
+                Self::Lit(value) => (0x0, crate::convert::IntoValue::<Vec<u8>>::into_value(value)),
+                Self::Neg(value) => (0x1, crate::convert::IntoValue::<Vec<u8>>::into_value(value)),
+            };
+            crate::Value::Choice(crate::TypeId::Std(0x7003), Box::new([]), id, Box::new(value))
+        }
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Ref<T>(pub u128, pub core::marker::PhantomData<(T, )>);
+    impl<T: crate::convert::FromValue<Vec<u8>>> crate::convert::FromValue<Vec<u8>> for Ref<T> {
+        fn from_value(value: crate::Value<Vec<u8>>) -> Result<Self, crate::convert::ConvertError> {
+            Ok(Self(crate::convert::FromValue::from_value(crate::convert::alias_value(value, &crate::TypeId::Std(0x7004))?)?, core::marker::PhantomData))
+        }
+    }
+    impl<T: crate::convert::IntoValue<Vec<u8>>> crate::convert::IntoValue<Vec<u8>> for Ref<T> {
+        fn value_type() -> crate::Type { crate::Type::Alias(crate::TypeId::Std(0x7004), Box::new([T::value_type(), ])) }
+        fn into_value(self) -> crate::Value<Vec<u8>> {
+            crate::Value::Alias(crate::TypeId::Std(0x7004), Box::new([T::value_type(), ]), Box::new(crate::convert::IntoValue::<Vec<u8>>::into_value(self.0)))
+        }
+    }
+}
+
+#[cfg(feature = "codegen")]
+#[test]
+fn codegen() {
+    use crate::{schema::*, codegen::*, convert::{FromValue, IntoValue}};
+
+    let registry = Registry::parse("
+        struct Node = 0x7001 { value: u, next: o<Node>, type: l<Node>, children: k<Expr Node> }
+        struct Tagged = 0x7002 <T> { id: u }
+        choice Expr = 0x7003 { 0 Lit(i), 1 Neg(Expr) }
+        alias Ref = 0x7004 <T> u
+    ").unwrap();
+    let out = generate(&registry, &Options { crate_path: "crate", bytes_type: "Vec<u8>" }).unwrap();
+    let strip = |code: &str| code.split_whitespace().collect::<String>();
+    assert_eq!(strip(&out), strip(GOLDEN));
+
+    let leaf = |value| generated::Node { value, next: None, r#type: Vec::new(), children: Default::default() };
+    let node = generated::Node {
+        value: 1,
+        next: Some(Box::new(leaf(2))),
+        r#type: Vec::new(),
+        children: [(generated::Expr::Lit(-1), leaf(3)), (generated::Expr::Lit(1), leaf(4))].into(),
+    };
+    let value: Value<Vec<u8>> = node.clone().into_value();
+    registry.check(&value).unwrap();
+    assert_eq!(generated::Node::from_value(value).unwrap(), node);
+    let expr = generated::Expr::Neg(Box::new(generated::Expr::Lit(1)));
+    assert_eq!(generated::Expr::from_value(expr.clone().into_value()).unwrap(), expr);
+    let tagged = generated::Tagged::<u128> { id: 1, _marker: core::marker::PhantomData };
+    assert_eq!(generated::Tagged::from_value(tagged.clone().into_value()).unwrap(), tagged);
+
+    let mut registry = Registry::new();
+    let _ = registry.insert(Def { name: "self".into(), type_id: TypeId::Std(0x7005), generics: seq![], kind: DefKind::Enum(seq![]) });
+    assert_eq!(generate(&registry, &DEFAULT_OPTIONS), Err(CodegenError::InvalidName("self".into())));
+
+    // names the generated code uses itself
+    let registry = Registry::parse("struct A = 0x7006 { _marker: u }").unwrap();
+    assert_eq!(generate(&registry, &DEFAULT_OPTIONS), Err(CodegenError::InvalidName("_marker".into())));
+    let registry = Registry::parse("choice A = 0x7006 { 0 _Marker(u) }").unwrap();
+    assert_eq!(generate(&registry, &DEFAULT_OPTIONS), Err(CodegenError::InvalidName("_Marker".into())));
+
+    let registry = Registry::parse("alias A = 0x7006 p<u u u u u u u u u>").unwrap();
+    assert_eq!(generate(&registry, &DEFAULT_OPTIONS), Err(CodegenError::TupleLen(9)));
 }
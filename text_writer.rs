@@ -54,7 +54,11 @@ impl<O: Output> Writer<O> {
     }
 
     fn v_uint_hex(&mut self, n: impl NumUnsigned) {
-
+        self.tag(Tag::Uint);
+        self.str("0x");
+        let bytes = n.to_u128().to_be_bytes();
+        let leading_zeros = bytes.iter().take_while(|byte| **byte == 0).count();
+        self.hex_bytes(&bytes[leading_zeros.min(bytes.len() - 1)..]);
     }
 
     fn hex_bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
@@ -85,6 +89,32 @@ impl<O: Output> Writer<O> {
         self.int_decimal(n as u8);
     }
 
+    // big-endian hex of a little-endian magnitude, at least one byte
+    fn hex_le(&mut self, le: &[u8]) {
+        let mut be: alloc::vec::Vec<u8> = le.iter().rev().copied().collect();
+        if be.is_empty() {
+            be.push(0);
+        }
+        self.str("0x");
+        self.hex_bytes(&*be);
+    }
+
+    fn v_big_uint(&mut self, le: &[u8]) {
+        self.tag(Tag::BigUint);
+        self.hex_le(bigint::trim_uint(le));
+    }
+
+    fn v_big_int(&mut self, le: &[u8]) {
+        self.tag(Tag::BigInt);
+        let le = bigint::trim_int(le);
+        if bigint::is_negative(le) {
+            self.ascii_byte(b'-');
+            self.hex_le(&bigint::negated(le));
+        } else {
+            self.hex_le(le);
+        }
+    }
+
     fn v_uints(&mut self, uints: &[impl NumUnsigned]) {
         self.tag(Tag::Uints);
         self.ascii_byte(b'[');
@@ -117,6 +147,13 @@ impl<O: Output> Writer<O> {
         self.ascii_byte(b'"');
     }
 
+    fn v_utf8<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
+        self.tag(Tag::Utf8);
+        self.ascii_byte(b'"');
+        self.utf8_bytes(bytes);
+        self.ascii_byte(b'"');
+    }
+
     fn begin_tuple_like<'a>(&'a mut self, tag: Tag) -> TupleWriter<'a, O> {
         self.tag(tag);
         TupleWriter::new(self)
@@ -140,6 +177,16 @@ impl<O: Output> Writer<O> {
                 TypeId::Std(id) => {
                     tl1.writer.v_uint_hex(*id);
                 }
+                TypeId::ThirdParty(namespace, id) => {
+                    let mut tl2 = tl1.writer.begin_tuple();
+                    {
+                        tl2.ahead_separator();
+                        tl2.writer.v_uint_hex(*namespace);
+                        tl2.ahead_separator();
+                        tl2.writer.v_uint_hex(*id);
+                    }
+                    tl2.end();
+                }
             };
         }
         tl1.end();
@@ -156,9 +203,12 @@ impl<O: Output> Writer<O> {
                 | Type::Uint
                 | Type::Int
                 | Type::Bool
+                | Type::BigUint
+                | Type::BigInt
                 | Type::Uints
                 | Type::Bytes
                 | Type::String
+                | Type::Utf8
                 | Type::Type
                 | Type::TypeId => {
                     let tl2 = tl1.writer.begin_tuple();
@@ -188,6 +238,22 @@ impl<O: Output> Writer<O> {
                     tl1.writer.v_type_id(type_id);
                 }
 
+                Type::Param(index) => {
+                    tl1.ahead_separator();
+                    tl1.writer.v_uint(*index);
+                }
+
+                Type::Map(key_type, value_type) => {
+                    let mut tl2 = tl1.writer.begin_tuple();
+                    {
+                        tl2.ahead_separator();
+                        tl2.writer.v_type(key_type);
+                        tl2.ahead_separator();
+                        tl2.writer.v_type(value_type);
+                    }
+                    tl2.end();
+                }
+
                 Type::Alias(type_id, generics)
                 | Type::Choice(type_id, generics)
                 | Type::Struct(type_id, generics) => {
@@ -196,7 +262,7 @@ impl<O: Output> Writer<O> {
                         tl2.ahead_separator();
                         tl2.writer.v_type_id(type_id);
                         tl2.ahead_separator();
-                        // tl2.writer.v_generics(generics);
+                        tl2.writer.v_generics(generics);
                     }
                     tl2.end();
                 }
@@ -204,6 +270,23 @@ impl<O: Output> Writer<O> {
         }
         tl1.end();
     }
+
+    fn v_generics(&mut self, generics: &[Type]) {
+        let mut tl1 = self.begin_tuple_like(Tag::Generics);
+        for generic in generics {
+            tl1.ahead_separator();
+            tl1.writer.v_type(generic);
+        }
+        tl1.end();
+    }
+
+    fn v_well_known(&mut self, name: &str, text: &str) {
+        self.ascii_byte(b'@');
+        self.str(name);
+        self.ascii_byte(b'"');
+        self.str(text);
+        self.ascii_byte(b'"');
+    }
 }
 
 struct TupleWriter<'a, O> {
@@ -239,24 +322,147 @@ impl<'a, O: Output> TupleWriter<'a, O> {
 }
 
 impl<O: Output> Writer<O> {
+    fn v_tuple<B: AsRef<[u8]> + ByteStorage>(&mut self, values: &[Value<B>]) {
+        let mut tl1 = self.begin_tuple();
+        for value in values {
+            tl1.ahead_separator();
+            tl1.writer.value(value);
+        }
+        tl1.end();
+    }
+
+    fn v_list<B: AsRef<[u8]> + ByteStorage>(&mut self, r#type: &Type, values: &[Value<B>]) {
+        let mut tl1 = self.begin_tuple_like(Tag::List);
+        {
+            tl1.ahead_separator();
+            tl1.writer.v_bool(!values.is_empty());
+            tl1.ahead_separator();
+            if values.is_empty() {
+                tl1.writer.v_type(r#type);
+            } else {
+                let mut tl2 = tl1.writer.begin_tuple_like(Tag::ListItems);
+                for value in values {
+                    tl2.ahead_separator();
+                    tl2.writer.value(value);
+                }
+                tl2.end();
+            }
+        }
+        tl1.end();
+    }
+
+    fn v_option<B: AsRef<[u8]> + ByteStorage>(&mut self, r#type: &Type, value: Option<&Value<B>>) {
+        let mut tl1 = self.begin_tuple_like(Tag::Option);
+        {
+            tl1.ahead_separator();
+            tl1.writer.v_bool(value.is_some());
+            tl1.ahead_separator();
+            match value {
+                Some(value) => tl1.writer.value(value),
+                None => tl1.writer.v_type(r#type),
+            }
+        }
+        tl1.end();
+    }
+
+    fn v_map<B: AsRef<[u8]> + ByteStorage>(&mut self, key_type: &Type, value_type: &Type, entries: &[(Value<B>, Value<B>)]) {
+        let mut tl1 = self.begin_tuple_like(Tag::Map);
+        {
+            tl1.ahead_separator();
+            tl1.writer.v_bool(!entries.is_empty());
+            tl1.ahead_separator();
+            if entries.is_empty() {
+                tl1.writer.v_generics(&[key_type.clone(), value_type.clone()]);
+            } else {
+                let mut tl2 = tl1.writer.begin_tuple_like(Tag::ListItems);
+                for (key, value) in ord::sorted_entries(entries, Value::read_cmp) {
+                    tl2.ahead_separator();
+                    tl2.writer.value(key);
+                    tl2.ahead_separator();
+                    tl2.writer.value(value);
+                }
+                tl2.end();
+            }
+        }
+        tl1.end();
+    }
+
+    fn v_alias<B: AsRef<[u8]> + ByteStorage>(&mut self, type_id: &TypeId, generics: &[Type], value: &Value<B>) {
+        let mut tl1 = self.begin_tuple_like(Tag::Alias);
+        {
+            tl1.ahead_separator();
+            tl1.writer.v_type_id(type_id);
+            tl1.ahead_separator();
+            tl1.writer.v_generics(generics);
+            tl1.ahead_separator();
+            tl1.writer.value(value);
+        }
+        tl1.end();
+    }
+
+    fn v_enum(&mut self, type_id: &TypeId, var_id: VariantId) {
+        let mut tl1 = self.begin_tuple_like(Tag::Enum);
+        {
+            tl1.ahead_separator();
+            tl1.writer.v_type_id(type_id);
+            tl1.ahead_separator();
+            tl1.writer.v_uint(var_id);
+        }
+        tl1.end();
+    }
+
+    fn v_choice<B: AsRef<[u8]> + ByteStorage>(&mut self, type_id: &TypeId, generics: &[Type], var_id: VariantId, value: &Value<B>) {
+        let mut tl1 = self.begin_tuple_like(Tag::Choice);
+        {
+            tl1.ahead_separator();
+            tl1.writer.v_type_id(type_id);
+            tl1.ahead_separator();
+            tl1.writer.v_generics(generics);
+            tl1.ahead_separator();
+            tl1.writer.v_uint(var_id);
+            tl1.ahead_separator();
+            tl1.writer.value(value);
+        }
+        tl1.end();
+    }
+
+    fn v_struct<B: AsRef<[u8]> + ByteStorage>(&mut self, type_id: &TypeId, generics: &[Type], values: &[Value<B>]) {
+        let mut tl1 = self.begin_tuple_like(Tag::Struct);
+        {
+            tl1.ahead_separator();
+            tl1.writer.v_type_id(type_id);
+            tl1.ahead_separator();
+            tl1.writer.v_generics(generics);
+            tl1.ahead_separator();
+            tl1.writer.v_tuple(values);
+        }
+        tl1.end();
+    }
+
     fn value<B: AsRef<[u8]> + ByteStorage>(&mut self, value: &Value<B>) {
         match value {
             Value::Uint(n) => self.v_uint(*n),
             Value::Int(n) => self.v_int(*n),
             Value::Bool(n) => self.v_bool(*n),
+            Value::BigUint(le) => self.v_big_uint(le),
+            Value::BigInt(le) => self.v_big_int(le),
             Value::Uints(uints) => self.v_uints(uints),
             Value::Bytes(bytes) => self.v_bytes(bytes),
             Value::String(chars) => self.v_string(chars),
-            // Value::Tuple(values) => self.v_tuple(values),
-            // Value::List(r#type, values) => self.v_list(r#type, values),
-            // Value::Option(r#type, value) => self.v_option(r#type, value.as_deref()),
-            // Value::Alias(type_id, generics, value) => self.v_alias(type_id, generics, value),
-            // Value::Enum(type_id, var_id) => self.v_enum(type_id, *var_id),
-            // Value::Choice(type_id, generics, var_id, value) => self.v_choice(type_id, generics, *var_id, value),
-            // Value::Struct(type_id, generics, values) => self.v_struct(type_id, generics, values),
+            Value::Utf8(bytes) => self.v_utf8(bytes),
+            Value::Tuple(values) => self.v_tuple(values),
+            Value::List(r#type, values) => self.v_list(r#type, values),
+            Value::Option(r#type, value) => self.v_option(r#type, value.as_deref()),
+            Value::Map(key_type, value_type, entries) => self.v_map(key_type, value_type, entries),
+            Value::Alias(type_id, generics, value2) => match well_known::text::render(value) {
+                Some((name, text)) => self.v_well_known(name, &text),
+                None => self.v_alias(type_id, generics, value2),
+            },
+            Value::Enum(type_id, var_id) => self.v_enum(type_id, *var_id),
+            Value::Choice(type_id, generics, var_id, value) => self.v_choice(type_id, generics, *var_id, value),
+            Value::Struct(type_id, generics, values) => self.v_struct(type_id, generics, values),
             Value::Type(r#type) => self.v_type(r#type),
             Value::TypeId(type_id) => self.v_type_id(type_id),
-            _ => unimplemented!()
         }
     }
 }
@@ -0,0 +1,34 @@
+// Decoding structs whose field count differs from the registered definition,
+// so that data written with older or newer schema versions can be mixed.
+
+use alloc::vec::Vec;
+use super::{*, byte_storage::Input, reader::{Reader, alloc_seq}, schema::*, defaults::Defaults};
+
+pub struct Tolerance<'a> {
+    pub registry: &'a Registry,
+    /// Keep trailing fields unknown to the registered definition instead of failing.
+    pub keep_unknown_fields: bool,
+}
+
+impl<B: AsRef<[u8]> + ByteStorage, I: Input<Storage = B>> Reader<'_, I> {
+    pub(crate) fn ic_struct_tolerant(&mut self, tolerance: &Tolerance, type_id: &TypeId, generics: &[Type], len: usize) -> Result<Box<[Value<B>]>> {
+        let fields: Option<&[Field]> = match tolerance.registry.get(type_id).map(|def| &def.kind) {
+            Some(DefKind::Struct(fields)) => Some(&fields[..]),
+            _ => None,
+        };
+        // structs without a definition are read as they are
+        let Some(fields) = fields else {
+            return alloc_seq(len, |_| self.i_struct());
+        };
+        if len > fields.len() && !tolerance.keep_unknown_fields {
+            return Err(Error::UnknownFields { type_id: type_id.clone(), len, exp_len: fields.len() });
+        }
+        let mut values = Vec::from(alloc_seq(len, |_| self.i_struct())?);
+        for (index, field) in fields.iter().enumerate().skip(len) {
+            let value = Defaults::new(Some(tolerance.registry), || self.bytes(0)).field(field, generics)?
+                .ok_or_else(|| Error::MissingFieldDefault { type_id: type_id.clone(), index })?;
+            values.push(value);
+        }
+        Ok(values.into_boxed_slice())
+    }
+}
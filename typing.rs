@@ -0,0 +1,221 @@
+use alloc::vec::Vec;
+use super::*;
+
+fn substitute_seq(types: &[Type], generics: &[Type]) -> Option<Box<[Type]>> {
+    types.iter().map(|r#type| r#type.substitute(generics)).collect()
+}
+
+impl Type {
+    /// Replaces every `Type::Param(i)` with `generics[i]`. Returns `None` if some parameter is unbound.
+    pub fn substitute(&self, generics: &[Type]) -> Option<Type> {
+        Some(match self {
+            Type::Param(index) => generics.get(*index as usize)?.clone(),
+
+            Type::Tuple(types) => Type::Tuple(substitute_seq(types, generics)?),
+            Type::List(type2) => Type::List(Box::new(type2.substitute(generics)?)),
+            Type::Option(type2) => Type::Option(Box::new(type2.substitute(generics)?)),
+            Type::Map(key_type, value_type) => {
+                Type::Map(Box::new(key_type.substitute(generics)?), Box::new(value_type.substitute(generics)?))
+            }
+
+            Type::Alias(type_id, generics2) => Type::Alias(type_id.clone(), substitute_seq(generics2, generics)?),
+            Type::Choice(type_id, generics2) => Type::Choice(type_id.clone(), substitute_seq(generics2, generics)?),
+            Type::Struct(type_id, generics2) => Type::Struct(type_id.clone(), substitute_seq(generics2, generics)?),
+
+            Type::Unknown
+            | Type::Uint
+            | Type::Int
+            | Type::Bool
+            | Type::BigUint
+            | Type::BigInt
+            | Type::Uints
+            | Type::Bytes
+            | Type::String
+            | Type::Utf8
+            | Type::Enum(_)
+            | Type::Type
+            | Type::TypeId => self.clone(),
+        })
+    }
+
+    /// Whether the type contains no `Type::Param`.
+    pub fn is_concrete(&self) -> bool {
+        match self {
+            Type::Param(_) => false,
+
+            Type::Tuple(types)
+            | Type::Alias(_, types)
+            | Type::Choice(_, types)
+            | Type::Struct(_, types) => types.iter().all(Type::is_concrete),
+            Type::List(type2)
+            | Type::Option(type2) => type2.is_concrete(),
+            Type::Map(key_type, value_type) => key_type.is_concrete() && value_type.is_concrete(),
+
+            Type::Unknown
+            | Type::Uint
+            | Type::Int
+            | Type::Bool
+            | Type::BigUint
+            | Type::BigInt
+            | Type::Uints
+            | Type::Bytes
+            | Type::String
+            | Type::Utf8
+            | Type::Enum(_)
+            | Type::Type
+            | Type::TypeId => true,
+        }
+    }
+}
+
+fn unify_seq(a: &[Type], b: &[Type]) -> Option<Box<[Type]>> {
+    if a.len() != b.len() {
+        return None;
+    }
+    a.iter().zip(b.iter()).map(|(a, b)| Type::unify(a, b)).collect()
+}
+
+impl Type {
+    /// The most specific type matching both `a` and `b`, where `Unknown` matches anything.
+    /// Composite types unify structurally, `None` if they disagree somewhere.
+    pub fn unify(a: &Type, b: &Type) -> Option<Type> {
+        Some(match (a, b) {
+            (Type::Unknown, other) | (other, Type::Unknown) => other.clone(),
+
+            (Type::Tuple(a), Type::Tuple(b)) => Type::Tuple(unify_seq(a, b)?),
+            (Type::List(a), Type::List(b)) => Type::List(Box::new(Type::unify(a, b)?)),
+            (Type::Option(a), Type::Option(b)) => Type::Option(Box::new(Type::unify(a, b)?)),
+            (Type::Map(a_key, a_value), Type::Map(b_key, b_value)) => {
+                Type::Map(Box::new(Type::unify(a_key, b_key)?), Box::new(Type::unify(a_value, b_value)?))
+            }
+
+            (Type::Alias(a_id, a), Type::Alias(b_id, b)) if a_id == b_id => Type::Alias(a_id.clone(), unify_seq(a, b)?),
+            (Type::Choice(a_id, a), Type::Choice(b_id, b)) if a_id == b_id => Type::Choice(a_id.clone(), unify_seq(a, b)?),
+            (Type::Struct(a_id, a), Type::Struct(b_id, b)) if a_id == b_id => Type::Struct(a_id.clone(), unify_seq(a, b)?),
+
+            (a, b) if a == b => a.clone(),
+            _ => return None,
+        })
+    }
+
+    /// Whether a value of this type can be used where `exp_type` is expected.
+    /// Since `Unknown` matches anything on both sides, this is the same as unifiable.
+    pub fn is_assignable_to(&self, exp_type: &Type) -> bool {
+        Type::unify(self, exp_type).is_some()
+    }
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    /// A list value from `items`, whose item type is `item_type` unified with the types of the
+    /// items, so that items carrying `Unknown` such as empty lists fit. `None` if one does not.
+    pub fn list(item_type: Type, items: impl IntoIterator<Item = Value<B>>) -> Option<Value<B>> {
+        let items: Box<[Value<B>]> = items.into_iter().collect();
+        let item_type = items.iter().try_fold(item_type, |r#type, item| Type::unify(&r#type, &item.as_type()))?;
+        Some(Value::List(item_type, items))
+    }
+}
+
+// the common type of `types`, see `Type::infer_from`
+fn infer(types: &[&Type]) -> Type {
+    let types: Vec<&Type> = types.iter().copied().filter(|r#type| **r#type != Type::Unknown).collect();
+    let Some(first) = types.first() else {
+        return Type::Unknown;
+    };
+    if types.iter().any(|r#type| matches!(r#type, Type::Option(_))) {
+        let types2: Vec<&Type> = types.iter().map(|r#type| match r#type {
+            Type::Option(type2) => &**type2,
+            r#type => *r#type,
+        }).collect();
+        return Type::Option(Box::new(infer(&types2)));
+    }
+    // the children of every type, if they are all of the same kind as `first`
+    let children = |f: fn(&Type) -> Option<&[Type]>| -> Option<Vec<&[Type]>> {
+        types.iter().map(|r#type| f(r#type)).collect()
+    };
+    let infer_seqs = |seqs: &[&[Type]]| -> Box<[Type]> {
+        let len = seqs.iter().map(|types| types.len()).max().unwrap_or(0);
+        (0..len).map(|i| {
+            let types: Vec<&Type> = seqs.iter().filter_map(|types| types.get(i)).collect();
+            let r#type = infer(&types);
+            match r#type {
+                Type::Option(_) => r#type,
+                r#type if types.len() < seqs.len() => Type::Option(Box::new(r#type)),
+                r#type => r#type,
+            }
+        }).collect()
+    };
+    match first {
+        Type::Tuple(_) => match children(|r#type| match r#type {
+            Type::Tuple(types) => Some(&**types),
+            _ => None,
+        }) {
+            Some(seqs) => Type::Tuple(infer_seqs(&seqs)),
+            None => Type::Unknown,
+        },
+        Type::List(_) => {
+            let types2: Option<Vec<&Type>> = types.iter().map(|r#type| match r#type {
+                Type::List(type2) => Some(&**type2),
+                _ => None,
+            }).collect();
+            match types2 {
+                Some(types2) => Type::List(Box::new(infer(&types2))),
+                None => Type::Unknown,
+            }
+        }
+        Type::Map(..) => {
+            let pairs: Option<Vec<(&Type, &Type)>> = types.iter().map(|r#type| match r#type {
+                Type::Map(key_type, value_type) => Some((&**key_type, &**value_type)),
+                _ => None,
+            }).collect();
+            match pairs {
+                Some(pairs) => {
+                    let (key_types, value_types): (Vec<&Type>, Vec<&Type>) = pairs.into_iter().unzip();
+                    Type::Map(Box::new(infer(&key_types)), Box::new(infer(&value_types)))
+                }
+                None => Type::Unknown,
+            }
+        }
+        Type::Alias(type_id, generics)
+        | Type::Choice(type_id, generics)
+        | Type::Struct(type_id, generics) => {
+            let same_def = types.iter().all(|r#type| match (first, r#type) {
+                (Type::Alias(..), Type::Alias(type_id2, generics2))
+                | (Type::Choice(..), Type::Choice(type_id2, generics2))
+                | (Type::Struct(..), Type::Struct(type_id2, generics2)) => {
+                    type_id2 == type_id && generics2.len() == generics.len()
+                }
+                _ => false,
+            });
+            if !same_def {
+                return Type::Unknown;
+            }
+            let seqs = children(|r#type| match r#type {
+                Type::Alias(_, generics) | Type::Choice(_, generics) | Type::Struct(_, generics) => Some(&**generics),
+                _ => None,
+            }).unwrap_or_default();
+            let generics = infer_seqs(&seqs);
+            match first {
+                Type::Alias(..) => Type::Alias(type_id.clone(), generics),
+                Type::Choice(..) => Type::Choice(type_id.clone(), generics),
+                _ => Type::Struct(type_id.clone(), generics),
+            }
+        }
+        first => {
+            if types.iter().all(|r#type| r#type == first) {
+                (*first).clone()
+            } else {
+                Type::Unknown
+            }
+        }
+    }
+}
+
+impl Type {
+    /// The most specific type describing all of `values`, e.g. for drafting a schema from samples.
+    /// Parts the values disagree on become `Unknown`, trailing tuple items missing from some values
+    /// become `Option`, and so do types that are sometimes `Option` and sometimes not.
+    pub fn infer_from<B: AsRef<[u8]> + ByteStorage>(values: &[Value<B>]) -> Type {
+        let types: Vec<Type> = values.iter().map(Value::as_type).collect();
+        infer(&types.iter().collect::<Vec<_>>())
+    }
+}
@@ -0,0 +1,289 @@
+// A view of an encoded value, decoded on demand instead of all at once.
+
+use core::{cmp::Ordering, ops::Range};
+use super::{*, byte_storage::{Input, Output, SliceInput}, reader::{Reader, OptionWithType}};
+
+/// An encoded value over borrowed bytes, whose structure is checked once at `new` and
+/// whose parts are decoded by the accessors as they are asked for.
+/// Type tables and references to shared values are not supported, see `Value::encode`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ValueRef<'a> {
+    tag: Tag,
+    bytes: &'a [u8],
+}
+
+// the value at the start of `bytes`, and the bytes after it
+fn split(bytes: &[u8]) -> Result<(ValueRef<'_>, &[u8])> {
+    let mut reader = Reader::<SliceInput>::new(bytes, DEFAULT_MAX_LENS);
+    let tag = reader.tag()?;
+    let tag = reader.c_skip(tag)?;
+    let rest = reader.into_rest().leak();
+    Ok((ValueRef { tag, bytes: &bytes[..bytes.len() - rest.len()] }, rest))
+}
+
+/// Items of a tuple, list or struct, see `ValueRef::as_tuple`.
+#[derive(Clone, Debug)]
+pub struct Items<'a> {
+    rest: &'a [u8],
+    len: usize,
+}
+
+impl<'a> Items<'a> {
+    fn new(rest: &'a [u8], len: usize) -> Items<'a> {
+        Items { rest, len }
+    }
+
+    fn empty() -> Items<'a> {
+        Items { rest: &[], len: 0 }
+    }
+}
+
+impl<'a> Iterator for Items<'a> {
+    type Item = ValueRef<'a>;
+
+    fn next(&mut self) -> Option<ValueRef<'a>> {
+        if self.len == 0 {
+            return None;
+        }
+        // the structure is checked at `ValueRef::new`
+        let (value, rest) = split(self.rest).ok()?;
+        self.rest = rest;
+        self.len -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl ExactSizeIterator for Items<'_> {}
+
+/// Entries of a map, see `ValueRef::map_iter`.
+#[derive(Clone, Debug)]
+pub struct Entries<'a> {
+    items: Items<'a>,
+}
+
+impl<'a> Iterator for Entries<'a> {
+    type Item = (ValueRef<'a>, ValueRef<'a>);
+
+    fn next(&mut self) -> Option<(ValueRef<'a>, ValueRef<'a>)> {
+        let key = self.items.next()?;
+        let value = self.items.next()?;
+        Some((key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.items.len / 2;
+        (len, Some(len))
+    }
+}
+
+impl ExactSizeIterator for Entries<'_> {}
+
+impl<'a> ValueRef<'a> {
+    pub fn new_with_max_lens(bytes: &'a [u8], max_lens: MaxLens) -> FullResult<ValueRef<'a>, &'a [u8]> {
+        let mut reader = Reader::<SliceInput>::new(bytes, max_lens);
+        let res = reader.tag().and_then(|tag| reader.c_skip(tag));
+        reader.finish_with(res).map(|tag| ValueRef { tag, bytes })
+    }
+
+    /// Checks that `bytes` holds exactly one value, without building it.
+    pub fn new(bytes: &'a [u8]) -> FullResult<ValueRef<'a>, &'a [u8]> {
+        Self::new_with_max_lens(bytes, DEFAULT_MAX_LENS)
+    }
+
+    pub fn tag(&self) -> &Tag {
+        &self.tag
+    }
+
+    /// The encoded value.
+    pub fn encoded(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    // where the value starts in `outer`, which it must be part of
+    pub(crate) fn offset_in(&self, outer: &[u8]) -> usize {
+        self.bytes.as_ptr() as usize - outer.as_ptr() as usize
+    }
+
+    pub fn to_value(&self) -> Result<Value<&'a [u8]>> {
+        Value::decode::<SliceInput>(self.bytes).map_err(|err| err.err)
+    }
+
+    // a reader after the tag, which must be `exp_tag`
+    fn payload(&self, exp_tag: Tag) -> Result<Reader<'static, SliceInput<'a>>> {
+        let mut reader = Reader::new(self.bytes, DEFAULT_MAX_LENS);
+        let tag = reader.tag()?;
+        if tag != exp_tag {
+            return Err(Error::ExpectedTypeMismatch { tag, exp_tag });
+        }
+        Ok(reader)
+    }
+
+    pub fn as_uint(&self) -> Result<u128> {
+        self.payload(Tag::Uint)?.c_uint()
+    }
+
+    pub fn as_int(&self) -> Result<i128> {
+        self.payload(Tag::Int)?.c_int()
+    }
+
+    pub fn as_bool(&self) -> Result<bool> {
+        self.payload(Tag::Bool)?.c_bool()
+    }
+
+    pub fn as_bytes(&self) -> Result<&'a [u8]> {
+        self.payload(Tag::Bytes)?.c_bytes()
+    }
+
+    pub fn as_str(&self) -> Result<&'a str> {
+        let bytes = self.payload(Tag::Utf8)?.c_utf8()?;
+        core::str::from_utf8(bytes).map_err(Error::Utf8)
+    }
+
+    pub fn as_tuple(&self) -> Result<Items<'a>> {
+        let mut reader = self.payload(Tag::Tuple)?;
+        let len = reader.h_tuple()?;
+        Ok(Items::new(reader.into_rest().leak(), len))
+    }
+
+    pub fn list_iter(&self) -> Result<Items<'a>> {
+        let mut reader = self.payload(Tag::List)?;
+        Ok(match reader.h_list()? {
+            OptionWithType::Some(len) => Items::new(reader.into_rest().leak(), len),
+            OptionWithType::None(_) => Items::empty(),
+        })
+    }
+
+    pub fn as_option(&self) -> Result<Option<ValueRef<'a>>> {
+        let mut reader = self.payload(Tag::Option)?;
+        Ok(match reader.h_option()? {
+            OptionWithType::Some(()) => Some(split(reader.into_rest().leak())?.0),
+            OptionWithType::None(_) => None,
+        })
+    }
+
+    pub fn map_iter(&self) -> Result<Entries<'a>> {
+        let mut reader = self.payload(Tag::Map)?;
+        let items = match reader.h_map()? {
+            OptionWithType::Some(len) => Items::new(reader.into_rest().leak(), len.saturating_mul(2)),
+            OptionWithType::None(_) => Items::empty(),
+        };
+        Ok(Entries { items })
+    }
+
+    pub fn as_alias(&self) -> Result<(TypeId, ValueRef<'a>)> {
+        let mut reader = self.payload(Tag::Alias)?;
+        let (type_id, _) = reader.h_alias()?;
+        Ok((type_id, split(reader.into_rest().leak())?.0))
+    }
+
+    pub fn as_enum(&self) -> Result<(TypeId, VariantId)> {
+        self.payload(Tag::Enum)?.c_enum()
+    }
+
+    pub fn as_choice(&self) -> Result<(TypeId, VariantId, ValueRef<'a>)> {
+        let mut reader = self.payload(Tag::Choice)?;
+        let (type_id, _, variant_id) = reader.h_choice()?;
+        Ok((type_id, variant_id, split(reader.into_rest().leak())?.0))
+    }
+
+    /// The type id and the fields of a struct.
+    pub fn as_struct(&self) -> Result<(TypeId, Items<'a>)> {
+        let mut reader = self.payload(Tag::Struct)?;
+        let (type_id, _, len) = reader.h_struct()?;
+        Ok((type_id, Items::new(reader.into_rest().leak(), len)))
+    }
+
+    /// The `index`th item of a tuple or field of a struct.
+    pub fn get(&self, index: usize) -> Result<Option<ValueRef<'a>>> {
+        let mut items = match self.tag {
+            Tag::Struct => self.as_struct()?.1,
+            _ => self.as_tuple()?,
+        };
+        Ok(items.nth(index))
+    }
+}
+
+/// Offsets of the items of an encoded list, for reaching any of them without reading
+/// the ones before. Can be persisted next to the list with `encode`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ListIndex {
+    // where each item starts in the list, then where the list ends
+    offsets: Box<[usize]>,
+}
+
+impl ListIndex {
+    pub fn build(list: &ValueRef) -> Result<ListIndex> {
+        let offsets = list.list_iter()?
+            .map(|item| item.offset_in(list.bytes))
+            .chain([list.bytes.len()])
+            .collect();
+        Ok(ListIndex { offsets })
+    }
+
+    pub fn len(&self) -> usize {
+        self.offsets.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Where the `index`th item is in the encoded list.
+    pub fn range(&self, index: usize) -> Option<Range<usize>> {
+        Some(*self.offsets.get(index)?..*self.offsets.get(index + 1)?)
+    }
+
+    /// The `index`th item of `list`, which must be the list the index was built from.
+    pub fn get<'a>(&self, list: &ValueRef<'a>, index: usize) -> Result<Option<ValueRef<'a>>> {
+        if *list.tag() != Tag::List || self.offsets.last() != Some(&list.bytes.len()) {
+            return Err(Error::ListIndexMismatch);
+        }
+        let Some(range) = self.range(index) else {
+            return Ok(None);
+        };
+        let bytes = list.bytes.get(range).ok_or(Error::ListIndexMismatch)?;
+        let (item, rest) = split(bytes).map_err(|_| Error::ListIndexMismatch)?;
+        if !rest.is_empty() {
+            return Err(Error::ListIndexMismatch);
+        }
+        Ok(Some(item))
+    }
+
+    /// Like `slice::binary_search_by` over the items of `list`, reading only the probed ones.
+    pub fn binary_search_by<'a, F: FnMut(&ValueRef<'a>) -> Ordering>(&self, list: &ValueRef<'a>, mut f: F) -> Result<core::result::Result<usize, usize>> {
+        let (mut low, mut high) = (0, self.len());
+        while low < high {
+            let mid = low + (high - low) / 2;
+            let item = self.get(list, mid)?.ok_or(Error::ListIndexMismatch)?;
+            match f(&item) {
+                Ordering::Less => low = mid + 1,
+                Ordering::Greater => high = mid,
+                Ordering::Equal => return Ok(Ok(mid)),
+            }
+        }
+        Ok(Err(low))
+    }
+
+    /// Encodes the offsets as a `Value::Uints`.
+    pub fn encode<O: Output>(&self) -> O::Storage {
+        Value::<&[u8]>::Uints(self.offsets.iter().map(|offset| *offset as u128).collect()).encode::<O>()
+    }
+
+    pub fn decode(buf: &[u8]) -> Result<ListIndex> {
+        let offsets = match Value::decode::<SliceInput>(buf).map_err(|err| err.err)? {
+            Value::Uints(offsets) => offsets,
+            value => return Err(Error::ExpectedTypeMismatch { tag: value.as_tag(), exp_tag: Tag::Uints }),
+        };
+        let offsets: Box<[usize]> = offsets.iter()
+            .map(|offset| usize::try_from(*offset).map_err(|_| Error::ListIndexMismatch))
+            .collect::<Result<_>>()?;
+        if offsets.is_empty() || offsets.windows(2).any(|pair| pair[0] >= pair[1]) {
+            return Err(Error::ListIndexMismatch);
+        }
+        Ok(ListIndex { offsets })
+    }
+}
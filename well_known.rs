@@ -0,0 +1,345 @@
+// Std definitions common enough to be built in. Each is an alias with one canonical payload:
+//
+//     U8         0x01  u       at most u8::MAX, likewise U16, U32 and U64 up to 0x04
+//     I8         0x05  i       within i8, likewise I16, I32 and I64 up to 0x08
+//     F32        0x09  b       4 bytes, little-endian IEEE 754
+//     F64        0x0a  b       8 bytes, little-endian IEEE 754
+//     TIMESTAMP  0x10  i       nanoseconds since the UNIX epoch, UTC, no leap seconds
+//     DURATION   0x11  u       nanoseconds
+//     UUID       0x12  b       16 bytes
+//     DECIMAL    0x13  p<i u>  mantissa and scale, meaning mantissa * 10^-scale; scale <= 255,
+//                              no trailing zeros in the mantissa unless the scale is 0
+//     IP_ADDR    0x14  b       4 bytes for IPv4 or 16 for IPv6, network order
+//
+// Values not following their canonical payload are still valid aliases, the accessors return `None` for them.
+// The reader is stricter about the fixed-width integers, an out of range payload is a decode error.
+
+use alloc::vec::Vec;
+use core::{time::Duration, net::{IpAddr, Ipv4Addr, Ipv6Addr}};
+use super::{*, schema::*};
+
+pub const U8: TypeId = TypeId::Std(0x01);
+pub const U16: TypeId = TypeId::Std(0x02);
+pub const U32: TypeId = TypeId::Std(0x03);
+pub const U64: TypeId = TypeId::Std(0x04);
+pub const I8: TypeId = TypeId::Std(0x05);
+pub const I16: TypeId = TypeId::Std(0x06);
+pub const I32: TypeId = TypeId::Std(0x07);
+pub const I64: TypeId = TypeId::Std(0x08);
+pub const F32: TypeId = TypeId::Std(0x09);
+pub const F64: TypeId = TypeId::Std(0x0a);
+pub const TIMESTAMP: TypeId = TypeId::Std(0x10);
+pub const DURATION: TypeId = TypeId::Std(0x11);
+pub const UUID: TypeId = TypeId::Std(0x12);
+pub const DECIMAL: TypeId = TypeId::Std(0x13);
+pub const IP_ADDR: TypeId = TypeId::Std(0x14);
+
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+
+impl Registry {
+    /// A registry with the definitions above.
+    pub fn well_known() -> Registry {
+        let mut registry = Registry::new();
+        for (name, type_id, r#type) in [
+            ("u8", U8, Type::Uint),
+            ("u16", U16, Type::Uint),
+            ("u32", U32, Type::Uint),
+            ("u64", U64, Type::Uint),
+            ("i8", I8, Type::Int),
+            ("i16", I16, Type::Int),
+            ("i32", I32, Type::Int),
+            ("i64", I64, Type::Int),
+            ("f32", F32, Type::Bytes),
+            ("f64", F64, Type::Bytes),
+            ("timestamp", TIMESTAMP, Type::Int),
+            ("duration", DURATION, Type::Uint),
+            ("uuid", UUID, Type::Bytes),
+            ("decimal", DECIMAL, Type::Tuple(Box::new([Type::Int, Type::Uint]))),
+            ("ip_addr", IP_ADDR, Type::Bytes),
+        ] {
+            let _ = registry.insert(Def { name: name.into(), type_id, generics: Box::new([]), kind: DefKind::Alias(r#type) });
+        }
+        registry
+    }
+}
+
+/// Rust type of a fixed-width integer alias.
+pub(crate) fn native_int(type_id: &TypeId) -> Option<&'static str> {
+    Some(match *type_id {
+        U8 => "u8",
+        U16 => "u16",
+        U32 => "u32",
+        U64 => "u64",
+        I8 => "i8",
+        I16 => "i16",
+        I32 => "i32",
+        I64 => "i64",
+        _ => return None,
+    })
+}
+
+fn alias<B: AsRef<[u8]> + ByteStorage>(type_id: TypeId, value: Value<B>) -> Value<B> {
+    Value::Alias(type_id, Box::new([]), Box::new(value))
+}
+
+impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
+    // payload of an alias of `type_id` without generics
+    fn well_known_payload(&self, type_id: &TypeId) -> Option<&Value<B>> {
+        match self {
+            Value::Alias(type_id2, generics, value) if type_id2 == type_id && generics.is_empty() => Some(value),
+            _ => None,
+        }
+    }
+
+    fn well_known_bytes<const N: usize>(&self, type_id: &TypeId) -> Option<[u8; N]> {
+        match self.well_known_payload(type_id)? {
+            Value::Bytes(bytes) => bytes.as_ref().try_into().ok(),
+            _ => None,
+        }
+    }
+
+    pub fn timestamp(nanos: i128) -> Value<B> {
+        alias(TIMESTAMP, Value::Int(nanos))
+    }
+
+    pub fn duration(duration: Duration) -> Value<B> {
+        alias(DURATION, Value::Uint(duration.as_nanos()))
+    }
+
+    /// Normalized to the canonical form, e.g. `(1200, 3)` is stored as `(12, 1)`.
+    pub fn decimal(mut mantissa: i128, mut scale: u8) -> Value<B> {
+        while scale > 0 && mantissa % 10 == 0 {
+            mantissa /= 10;
+            scale -= 1;
+        }
+        alias(DECIMAL, Value::Tuple(Box::new([Value::Int(mantissa), Value::Uint(scale.into())])))
+    }
+
+    pub fn as_f32(&self) -> Option<f32> {
+        self.well_known_bytes(&F32).map(f32::from_le_bytes)
+    }
+
+    pub fn as_f64(&self) -> Option<f64> {
+        self.well_known_bytes(&F64).map(f64::from_le_bytes)
+    }
+
+    /// Nanoseconds since the UNIX epoch.
+    pub fn as_timestamp(&self) -> Option<i128> {
+        match self.well_known_payload(&TIMESTAMP)? {
+            Value::Int(nanos) => Some(*nanos),
+            _ => None,
+        }
+    }
+
+    pub fn as_duration(&self) -> Option<Duration> {
+        match self.well_known_payload(&DURATION)? {
+            Value::Uint(nanos) => {
+                let secs = (nanos / NANOS_PER_SEC).try_into().ok()?;
+                Some(Duration::new(secs, (nanos % NANOS_PER_SEC) as u32))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn as_uuid(&self) -> Option<[u8; 16]> {
+        self.well_known_bytes(&UUID)
+    }
+
+    /// Mantissa and scale, only if canonical.
+    pub fn as_decimal(&self) -> Option<(i128, u8)> {
+        match self.well_known_payload(&DECIMAL)? {
+            Value::Tuple(values) => match &**values {
+                [Value::Int(mantissa), Value::Uint(scale)] => {
+                    let scale = u8::try_from(*scale).ok()?;
+                    (scale == 0 || mantissa % 10 != 0).then_some((*mantissa, scale))
+                }
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn as_ip_addr(&self) -> Option<IpAddr> {
+        match self.well_known_payload(&IP_ADDR)? {
+            Value::Bytes(bytes) => match bytes.as_ref().len() {
+                4 => Some(IpAddr::V4(Ipv4Addr::from(<[u8; 4]>::try_from(bytes.as_ref()).ok()?))),
+                16 => Some(IpAddr::V6(Ipv6Addr::from(<[u8; 16]>::try_from(bytes.as_ref()).ok()?))),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
+
+macro_rules! impl_fixed_width {
+    ($($ty:ident $as_ty:ident $type_id:ident $name:ident,)*) => {
+        impl<B: AsRef<[u8]> + ByteStorage> Value<B> {$(
+            pub fn $ty(n: $ty) -> Value<B> {
+                alias($type_id, Value::$name(n.into()))
+            }
+
+            pub fn $as_ty(&self) -> Option<$ty> {
+                match self.well_known_payload(&$type_id)? {
+                    Value::$name(n) => (*n).try_into().ok(),
+                    _ => None,
+                }
+            }
+        )*}
+    };
+}
+
+// false for aliases of fixed-width integers whose payload is out of range
+pub(crate) fn in_range<B: AsRef<[u8]> + ByteStorage>(value: &Value<B>) -> bool {
+    let Value::Alias(type_id, generics, _) = value else {
+        return true;
+    };
+    if !generics.is_empty() {
+        return true;
+    }
+    match *type_id {
+        U8 => value.as_u8().is_some(),
+        U16 => value.as_u16().is_some(),
+        U32 => value.as_u32().is_some(),
+        U64 => value.as_u64().is_some(),
+        I8 => value.as_i8().is_some(),
+        I16 => value.as_i16().is_some(),
+        I32 => value.as_i32().is_some(),
+        I64 => value.as_i64().is_some(),
+        _ => true,
+    }
+}
+
+impl_fixed_width! {
+    u8 as_u8 U8 Uint,
+    u16 as_u16 U16 Uint,
+    u32 as_u32 U32 Uint,
+    u64 as_u64 U64 Uint,
+    i8 as_i8 I8 Int,
+    i16 as_i16 I16 Int,
+    i32 as_i32 I32 Int,
+    i64 as_i64 I64 Int,
+}
+
+impl<B: AsRef<[u8]> + ByteStorage + From<Vec<u8>>> Value<B> {
+    fn well_known_bytes_value(type_id: TypeId, bytes: &[u8]) -> Value<B> {
+        alias(type_id, Value::Bytes(bytes.to_vec().into()))
+    }
+
+    pub fn f32(x: f32) -> Value<B> {
+        Self::well_known_bytes_value(F32, &x.to_le_bytes())
+    }
+
+    pub fn f64(x: f64) -> Value<B> {
+        Self::well_known_bytes_value(F64, &x.to_le_bytes())
+    }
+
+    pub fn uuid(uuid: [u8; 16]) -> Value<B> {
+        Self::well_known_bytes_value(UUID, &uuid)
+    }
+
+    pub fn ip_addr(addr: IpAddr) -> Value<B> {
+        match addr {
+            IpAddr::V4(addr) => Self::well_known_bytes_value(IP_ADDR, &addr.octets()),
+            IpAddr::V6(addr) => Self::well_known_bytes_value(IP_ADDR, &addr.octets()),
+        }
+    }
+}
+
+#[cfg(feature = "text-writer")]
+pub(crate) mod text {
+    use alloc::{string::String, format};
+    use core::fmt::Write;
+    use super::*;
+
+    // (year, month, day) of days since 1970-01-01, from http://howardhinnant.github.io/date_algorithms.html
+    fn civil_from_days(days: i128) -> (i128, u32, u32) {
+        let z = days + 719468;
+        let era = z.div_euclid(146097);
+        let doe = z.rem_euclid(146097);
+        let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+        let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+        let mp = (5 * doy + 2) / 153;
+        let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+        let month = (if mp < 10 { mp + 3 } else { mp - 9 }) as u32;
+        (yoe + era * 400 + (month <= 2) as i128, month, day)
+    }
+
+    // `.` and the nanoseconds without trailing zeros, nothing if zero
+    fn fraction(s: &mut String, nanos: u128) {
+        if nanos != 0 {
+            let digits = format!("{nanos:09}");
+            s.push('.');
+            s.push_str(digits.trim_end_matches('0'));
+        }
+    }
+
+    fn timestamp(nanos: i128) -> String {
+        let secs = nanos.div_euclid(NANOS_PER_SEC as i128);
+        let (days, secs_of_day) = (secs.div_euclid(86400), secs.rem_euclid(86400));
+        let (year, month, day) = civil_from_days(days);
+        let mut s = format!(
+            "{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}",
+            secs_of_day / 3600, secs_of_day / 60 % 60, secs_of_day % 60,
+        );
+        fraction(&mut s, nanos.rem_euclid(NANOS_PER_SEC as i128) as u128);
+        s.push('Z');
+        s
+    }
+
+    fn duration(nanos: u128) -> String {
+        let mut s = format!("{}", nanos / NANOS_PER_SEC);
+        fraction(&mut s, nanos % NANOS_PER_SEC);
+        s.push('s');
+        s
+    }
+
+    fn uuid(bytes: [u8; 16]) -> String {
+        let mut s = String::with_capacity(36);
+        for (i, byte) in bytes.iter().enumerate() {
+            if matches!(i, 4 | 6 | 8 | 10) {
+                s.push('-');
+            }
+            let _ = write!(s, "{byte:02x}");
+        }
+        s
+    }
+
+    fn decimal(mantissa: i128, scale: u8) -> String {
+        let digits = format!("{:01$}", mantissa.unsigned_abs(), scale as usize + 1);
+        let (int, frac) = digits.split_at(digits.len() - scale as usize);
+        let sign = if mantissa < 0 { "-" } else { "" };
+        if frac.is_empty() {
+            format!("{sign}{int}")
+        } else {
+            format!("{sign}{int}.{frac}")
+        }
+    }
+
+    /// Name and text of a canonical well-known value, rendered by the text writer as `@name"text"`.
+    pub(crate) fn render<B: AsRef<[u8]> + ByteStorage>(value: &Value<B>) -> Option<(&'static str, String)> {
+        let Value::Alias(type_id, ..) = value else {
+            return None;
+        };
+        Some(match *type_id {
+            U8 => ("u8", format!("{}", value.as_u8()?)),
+            U16 => ("u16", format!("{}", value.as_u16()?)),
+            U32 => ("u32", format!("{}", value.as_u32()?)),
+            U64 => ("u64", format!("{}", value.as_u64()?)),
+            I8 => ("i8", format!("{}", value.as_i8()?)),
+            I16 => ("i16", format!("{}", value.as_i16()?)),
+            I32 => ("i32", format!("{}", value.as_i32()?)),
+            I64 => ("i64", format!("{}", value.as_i64()?)),
+            F32 => ("f32", format!("{}", value.as_f32()?)),
+            F64 => ("f64", format!("{}", value.as_f64()?)),
+            TIMESTAMP => ("timestamp", timestamp(value.as_timestamp()?)),
+            DURATION => ("duration", duration(value.as_duration()?.as_nanos())),
+            UUID => ("uuid", uuid(value.as_uuid()?)),
+            DECIMAL => {
+                let (mantissa, scale) = value.as_decimal()?;
+                ("decimal", decimal(mantissa, scale))
+            }
+            IP_ADDR => ("ip_addr", format!("{}", value.as_ip_addr()?)),
+            _ => return None,
+        })
+    }
+}
@@ -1,24 +1,108 @@
-use crate::{*, byte_storage::Output, leb128::*};
+use alloc::{vec::Vec, collections::BTreeMap};
+use crate::{*, byte_storage::{Output, SliceInput, VecOutput}, leb128::*};
 
 // TODO writer error?
 
-pub(crate) struct Writer<O> {
-    output: O,
+fn uleb128_len(n: u128) -> usize {
+    ((128 - n.leading_zeros()) as usize).div_ceil(7).max(1)
+}
+
+// bits of the largest uint, at least one so that a packed length is bounded by the input
+pub(crate) fn uints_width(uints: &[u128]) -> u32 {
+    uints.iter().max().map_or(0, |n| 128 - n.leading_zeros()).max(1)
+}
+
+// the smallest encoding, the plain one on ties
+pub(crate) fn uints_tag(uints: &[u128]) -> Tag {
+    let plain: usize = uints.iter().map(|n| uleb128_len(*n)).sum();
+    let delta = uints.is_sorted().then(|| {
+        uints.iter().scan(0, |prev, n| {
+            let len = uleb128_len(n - *prev);
+            *prev = *n;
+            Some(len)
+        }).sum::<usize>()
+    });
+    let width = uints_width(uints);
+    let packed = uleb128_len(width.into()) + (uints.len() * width as usize).div_ceil(8);
+    if let Some(delta) = delta && delta < plain && delta <= packed {
+        Tag::UintsDelta
+    } else if packed < plain {
+        Tag::UintsPacked
+    } else {
+        Tag::Uints
+    }
+}
+
+// where the writer puts the bytes, an `Output` or something consuming them as they come
+pub(crate) trait Sink {
+    fn byte(&mut self, byte: u8);
+    fn bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B);
+
+    // around every value written, for sinks keeping where values are
+    #[inline]
+    fn value_start(&mut self) {}
+
+    #[inline]
+    fn value_end(&mut self) {}
+}
+
+impl<O: Output> Sink for O {
+    #[inline]
+    fn byte(&mut self, byte: u8) {
+        Output::byte(self, byte);
+    }
+
+    #[inline]
+    fn bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
+        Output::bytes(self, bytes);
+    }
+}
+
+pub(crate) struct Writer<S> {
+    output: S,
+    // indices into the type table, empty without one
+    type_refs: BTreeMap<Type, usize>,
+    type_id_refs: BTreeMap<TypeId, usize>,
+    // for every value in the order they are written, which of `shared` it is if it is to be
+    // shared, and the position after the values in it, see `encode_shared`
+    shared_values: Vec<(Option<usize>, usize)>,
+    shared_pos: usize,
+    // indices of the values to share once written
+    shared: Vec<Option<usize>>,
+    shared_len: usize,
 }
 
 impl<O: Output> Writer<O> {
+    pub(crate) fn new() -> Writer<O> {
+        Self::with_sink(Default::default())
+    }
+
+    pub(crate) fn into_inner(self) -> O::Storage {
+        self.output.leak()
+    }
+}
+
+impl<S: Sink> Writer<S> {
     // begin wrapper impls
 
-    fn new() -> Writer<O> {
-        Writer { output: Default::default() }
+    pub(crate) fn with_sink(output: S) -> Writer<S> {
+        Writer {
+            output,
+            type_refs: BTreeMap::new(),
+            type_id_refs: BTreeMap::new(),
+            shared_values: Vec::new(),
+            shared_pos: 0,
+            shared: Vec::new(),
+            shared_len: 0,
+        }
     }
 
-    fn into_inner(self) -> O::Storage {
-        self.output.leak()
+    pub(crate) fn into_sink(self) -> S {
+        self.output
     }
 
     #[inline]
-    fn bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
+    pub(crate) fn bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
         self.output.bytes(bytes);
     }
 
@@ -48,7 +132,17 @@ impl<O: Output> Writer<O> {
         self.uleb128(n as u8);
     }
 
-    fn v_uints(&mut self, uints: &[impl NumUnsigned]) {
+    fn v_big_uint(&mut self, le: &[u8]) {
+        self.tag(Tag::BigUint);
+        self.uleb128_big(le);
+    }
+
+    fn v_big_int(&mut self, le: &[u8]) {
+        self.tag(Tag::BigInt);
+        self.sleb128_big(le);
+    }
+
+    fn v_uints_plain(&mut self, uints: &[u128]) {
         self.tag(Tag::Uints);
         self.uleb128(uints.len());
         for n in uints {
@@ -56,6 +150,45 @@ impl<O: Output> Writer<O> {
         }
     }
 
+    // sorted only
+    fn v_uints_delta(&mut self, uints: &[u128]) {
+        self.tag(Tag::UintsDelta);
+        self.uleb128(uints.len());
+        let mut prev = 0;
+        for n in uints {
+            self.uleb128(n - prev);
+            prev = *n;
+        }
+    }
+
+    fn v_uints_packed(&mut self, uints: &[u128], width: u32) {
+        self.tag(Tag::UintsPacked);
+        self.uleb128(uints.len());
+        self.uleb128(width as u8);
+        let (mut byte, mut filled) = (0u8, 0);
+        for n in uints {
+            for i in 0..width {
+                byte |= (((n >> i) & 1) as u8) << filled;
+                filled += 1;
+                if filled == 8 {
+                    self.byte(byte);
+                    (byte, filled) = (0, 0);
+                }
+            }
+        }
+        if filled > 0 {
+            self.byte(byte);
+        }
+    }
+
+    fn v_uints(&mut self, uints: &[u128]) {
+        match uints_tag(uints) {
+            Tag::UintsDelta => self.v_uints_delta(uints),
+            Tag::UintsPacked => self.v_uints_packed(uints, uints_width(uints)),
+            _ => self.v_uints_plain(uints),
+        }
+    }
+
     fn v_bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
         self.tag(Tag::Bytes);
         self.uleb128(bytes.as_ref().len());
@@ -70,6 +203,12 @@ impl<O: Output> Writer<O> {
         }
     }
 
+    fn v_utf8<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
+        self.tag(Tag::Utf8);
+        self.uleb128(bytes.as_ref().len());
+        self.bytes(bytes);
+    }
+
     fn h_tuple_like_need_values(&mut self, tag: Tag, len: impl NumUnsigned) {
         self.tag(tag);
         self.uleb128(len);
@@ -79,7 +218,11 @@ impl<O: Output> Writer<O> {
         self.h_tuple_like_need_values(Tag::Tuple, len);
     }
 
-    fn v_type_id(&mut self, type_id: &TypeId) {
+    pub(crate) fn v_type_id(&mut self, type_id: &TypeId) {
+        if let Some(&index) = self.type_id_refs.get(type_id) {
+            self.h_tuple_like_need_values(Tag::TypeRef, index);
+            return;
+        }
         self.h_tuple_like_need_values(Tag::TypeId, 2u8);
         self.v_uint(type_id.as_type_id_tag() as u8);
         match type_id {
@@ -89,10 +232,19 @@ impl<O: Output> Writer<O> {
             TypeId::Std(id) => {
                 self.v_uint(*id);
             }
+            TypeId::ThirdParty(namespace, id) => {
+                self.h_tuple_need_values(2u8);
+                self.v_uint(*namespace);
+                self.v_uint(*id);
+            }
         };
     }
 
-    fn v_type(&mut self, r#type: &Type) {
+    pub(crate) fn v_type(&mut self, r#type: &Type) {
+        if let Some(&index) = self.type_refs.get(r#type) {
+            self.h_tuple_like_need_values(Tag::TypeRef, index);
+            return;
+        }
         self.h_tuple_like_need_values(Tag::Type, 2u8);
         self.v_uint(r#type.as_type_tag() as u8);
         match r#type {
@@ -100,9 +252,12 @@ impl<O: Output> Writer<O> {
             | Type::Uint
             | Type::Int
             | Type::Bool
+            | Type::BigUint
+            | Type::BigInt
             | Type::Uints
             | Type::Bytes
             | Type::String
+            | Type::Utf8
             | Type::Type
             | Type::TypeId => {
                 self.h_tuple_need_values(0u8);
@@ -125,6 +280,16 @@ impl<O: Output> Writer<O> {
                 self.v_type_id(type_id);
             }
 
+            Type::Param(index) => {
+                self.v_uint(*index);
+            }
+
+            Type::Map(key_type, value_type) => {
+                self.h_tuple_need_values(2u8);
+                self.v_type(key_type);
+                self.v_type(value_type);
+            }
+
             Type::Alias(type_id, generics)
             | Type::Choice(type_id, generics)
             | Type::Struct(type_id, generics) => {
@@ -154,6 +319,19 @@ impl<O: Output> Writer<O> {
         }
     }
 
+    fn h_map_need_entries(&mut self, key_type: &Type, value_type: &Type, len: impl NumUnsigned) {
+        self.h_tuple_like_need_values(Tag::Map, 2u8);
+        let is_some = !len.all_zero();
+        self.v_bool(is_some);
+        if is_some {
+            self.h_tuple_like_need_values(Tag::ListItems, len);
+        } else {
+            self.h_tuple_like_need_values(Tag::Generics, 2u8);
+            self.v_type(key_type);
+            self.v_type(value_type);
+        }
+    }
+
     fn v_generics(&mut self, generics: &[Type]) {
         self.h_tuple_like_need_values(Tag::Generics, generics.len());
         for generic in generics {
@@ -188,7 +366,7 @@ impl<O: Output> Writer<O> {
     }
 }
 
-impl<O: Output> Writer<O> {
+impl<S: Sink> Writer<S> {
     fn v_tuple<B: AsRef<[u8]> + ByteStorage>(&mut self, values: &[Value<B>]) {
         self.h_tuple_need_values(values.len());
         for value in values {
@@ -210,9 +388,23 @@ impl<O: Output> Writer<O> {
         }
     }
 
+    fn v_map<B: AsRef<[u8]> + ByteStorage>(&mut self, key_type: &Type, value_type: &Type, entries: &[(Value<B>, Value<B>)]) {
+        self.h_map_need_entries(key_type, value_type, entries.len());
+        for (key, value) in ord::sorted_entries(entries, Value::read_cmp) {
+            self.value(key);
+            self.value(value);
+        }
+    }
+
     fn v_alias<B: AsRef<[u8]> + ByteStorage>(&mut self, type_id: &TypeId, generics: &[Type], value: &Value<B>) {
         self.h_alias_need_value(type_id, generics);
-        self.value(value);
+        // payloads of fixed-width integers are read as bare integers, so they are neither shared
+        // nor counted, see `encode_shared`
+        if generics.is_empty() && well_known::native_int(type_id).is_some() {
+            self.value_unshared(value);
+        } else {
+            self.value(value);
+        }
     }
 
     fn v_choice<B: AsRef<[u8]> + ByteStorage>(&mut self, type_id: &TypeId, generics: &[Type], var_id: VariantId, value: &Value<B>) {
@@ -227,17 +419,49 @@ impl<O: Output> Writer<O> {
         }
     }
 
-    fn value<B: AsRef<[u8]> + ByteStorage>(&mut self, value: &Value<B>) {
+    // indices are given in the order shared values end, as the reader sees them
+    pub(crate) fn value<B: AsRef<[u8]> + ByteStorage>(&mut self, value: &Value<B>) {
+        self.output.value_start();
+        let shared = self.shared_values.get(self.shared_pos).copied();
+        self.shared_pos += 1;
+        match shared {
+            Some((Some(id), after)) => {
+                self.h_tuple_like_need_values(Tag::Shared, 2u8);
+                match self.shared[id] {
+                    Some(index) => {
+                        self.v_bool(false);
+                        self.v_uint(index);
+                        // the values in it are not written
+                        self.shared_pos = after;
+                    }
+                    None => {
+                        self.v_bool(true);
+                        self.value_unshared(value);
+                        self.shared[id] = Some(self.shared_len);
+                        self.shared_len += 1;
+                    }
+                }
+            }
+            _ => self.value_unshared(value),
+        }
+        self.output.value_end();
+    }
+
+    fn value_unshared<B: AsRef<[u8]> + ByteStorage>(&mut self, value: &Value<B>) {
         match value {
             Value::Uint(n) => self.v_uint(*n),
             Value::Int(n) => self.v_int(*n),
             Value::Bool(n) => self.v_bool(*n),
+            Value::BigUint(le) => self.v_big_uint(le),
+            Value::BigInt(le) => self.v_big_int(le),
             Value::Uints(uints) => self.v_uints(uints),
             Value::Bytes(bytes) => self.v_bytes(bytes),
             Value::String(chars) => self.v_string(chars),
+            Value::Utf8(bytes) => self.v_utf8(bytes),
             Value::Tuple(values) => self.v_tuple(values),
             Value::List(r#type, values) => self.v_list(r#type, values),
             Value::Option(r#type, value) => self.v_option(r#type, value.as_deref()),
+            Value::Map(key_type, value_type, entries) => self.v_map(key_type, value_type, entries),
             Value::Alias(type_id, generics, value) => self.v_alias(type_id, generics, value),
             Value::Enum(type_id, var_id) => self.v_enum(type_id, *var_id),
             Value::Choice(type_id, generics, var_id, value) => self.v_choice(type_id, generics, *var_id, value),
@@ -248,10 +472,224 @@ impl<O: Output> Writer<O> {
     }
 }
 
+enum TypeTableEntry<'a> {
+    Type(&'a Type),
+    TypeId(&'a TypeId),
+}
+
+// occurrences of the types and type ids the writer would write, in first occurrence order
+// with the parts of a type before it
+#[derive(Default)]
+struct TypeCounts<'a> {
+    types: BTreeMap<&'a Type, usize>,
+    type_ids: BTreeMap<&'a TypeId, usize>,
+    order: Vec<TypeTableEntry<'a>>,
+}
+
+impl<'a> TypeCounts<'a> {
+    fn type_id(&mut self, type_id: &'a TypeId) {
+        let count = self.type_ids.entry(type_id).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            self.order.push(TypeTableEntry::TypeId(type_id));
+        }
+    }
+
+    fn types(&mut self, types: &'a [Type]) {
+        for r#type in types {
+            self.r#type(r#type);
+        }
+    }
+
+    fn r#type(&mut self, r#type: &'a Type) {
+        match r#type {
+            Type::Tuple(types) => self.types(types),
+            Type::List(type2)
+            | Type::Option(type2) => self.r#type(type2),
+            Type::Map(key_type, value_type) => {
+                self.r#type(key_type);
+                self.r#type(value_type);
+            }
+            Type::Enum(type_id) => self.type_id(type_id),
+            Type::Alias(type_id, generics)
+            | Type::Choice(type_id, generics)
+            | Type::Struct(type_id, generics) => {
+                self.type_id(type_id);
+                self.types(generics);
+            }
+            _ => {}
+        }
+        let count = self.types.entry(r#type).or_insert(0);
+        *count += 1;
+        if *count == 1 {
+            self.order.push(TypeTableEntry::Type(r#type));
+        }
+    }
+
+    fn values<B: AsRef<[u8]> + ByteStorage>(&mut self, values: &'a [Value<B>]) {
+        for value in values {
+            self.value(value);
+        }
+    }
+
+    fn value<B: AsRef<[u8]> + ByteStorage>(&mut self, value: &'a Value<B>) {
+        match value {
+            Value::Tuple(values) => self.values(values),
+            Value::List(r#type, values) => {
+                if values.is_empty() {
+                    self.r#type(r#type);
+                } else {
+                    self.values(values);
+                }
+            }
+            Value::Option(r#type, value) => match value {
+                Some(value) => self.value(value),
+                None => self.r#type(r#type),
+            },
+            Value::Map(key_type, value_type, entries) => {
+                if entries.is_empty() {
+                    self.r#type(key_type);
+                    self.r#type(value_type);
+                }
+                for (key, value) in entries.iter() {
+                    self.value(key);
+                    self.value(value);
+                }
+            }
+            Value::Alias(type_id, generics, value)
+            | Value::Choice(type_id, generics, _, value) => {
+                self.type_id(type_id);
+                self.types(generics);
+                self.value(value);
+            }
+            Value::Enum(type_id, _) => self.type_id(type_id),
+            Value::Struct(type_id, generics, values) => {
+                self.type_id(type_id);
+                self.types(generics);
+                self.values(values);
+            }
+            Value::Type(r#type) => self.r#type(r#type),
+            Value::TypeId(type_id) => self.type_id(type_id),
+            _ => {}
+        }
+    }
+}
+
+// `Z 02 F0 U<index>` for indices below 128, and the `Z 02 F1` before a shared value
+const SHARED_REF_LEN: usize = 6;
+const SHARED_DEF_LEN: usize = 4;
+
+// the encoding of a value, and where every value in it is in the order they are written
+#[derive(Default)]
+struct Spans {
+    buf: Vec<u8>,
+    // start, end and the position after the values in it
+    spans: Vec<(usize, usize, usize)>,
+    open: Vec<usize>,
+}
+
+impl Sink for Spans {
+    #[inline]
+    fn byte(&mut self, byte: u8) {
+        self.buf.push(byte);
+    }
+
+    #[inline]
+    fn bytes<B: AsRef<[u8]> + ByteStorage>(&mut self, bytes: B) {
+        self.buf.extend_from_slice(bytes.as_ref());
+    }
+
+    fn value_start(&mut self) {
+        self.open.push(self.spans.len());
+        self.spans.push((self.buf.len(), 0, 0));
+    }
+
+    fn value_end(&mut self) {
+        if let Some(pos) = self.open.pop() {
+            self.spans[pos].1 = self.buf.len();
+            self.spans[pos].2 = self.spans.len();
+        }
+    }
+}
+
 impl<B: AsRef<[u8]> + ByteStorage> Value<B> {
     pub fn encode<O: Output>(&self) -> O::Storage {
         let mut writer = Writer::<O>::new();
         writer.value(self);
         writer.into_inner()
     }
+
+    /// The only form of the value `decode_canonical` accepts, which is what `encode` writes.
+    /// Fails for values that have none, such as maps with duplicate keys.
+    pub fn canonical_encode(&self) -> Result<Vec<u8>> {
+        let buf = self.encode::<VecOutput>();
+        let _ = Value::<&[u8]>::decode_canonical::<SliceInput>(&buf).map_err(|err| err.err)?;
+        Ok(buf)
+    }
+
+    /// Like `encode`, with the types and type ids written more than once moved to a leading
+    /// type table and referred to by index. Decoded by `decode` like any other document.
+    pub fn encode_with_type_table<O: Output>(&self) -> O::Storage {
+        let mut counts = TypeCounts::default();
+        counts.value(self);
+        let entries: Vec<_> = counts.order.into_iter().filter(|entry| match entry {
+            TypeTableEntry::Type(r#type) => counts.types[r#type] > 1,
+            TypeTableEntry::TypeId(type_id) => counts.type_ids[type_id] > 1,
+        }).collect();
+        let mut writer = Writer::<O>::new();
+        if !entries.is_empty() {
+            writer.h_tuple_like_need_values(Tag::TypeTable, entries.len());
+            for (index, entry) in entries.into_iter().enumerate() {
+                match entry {
+                    TypeTableEntry::Type(r#type) => {
+                        writer.v_type(r#type);
+                        let _ = writer.type_refs.insert(r#type.clone(), index);
+                    }
+                    TypeTableEntry::TypeId(type_id) => {
+                        writer.v_type_id(type_id);
+                        let _ = writer.type_id_refs.insert(type_id.clone(), index);
+                    }
+                }
+            }
+        }
+        writer.value(self);
+        writer.into_inner()
+    }
+
+    /// Like `encode`, with values written more than once shared by the first occurrence
+    /// and referred back to by the others, where that is shorter. Decoded by `decode_shared`,
+    /// which copies shared values for each reference, see `MaxLens::expansion`.
+    pub fn encode_shared<O: Output>(&self) -> O::Storage {
+        let mut writer = Writer::with_sink(Spans::default());
+        writer.value(self);
+        let Spans { buf, spans, .. } = writer.into_sink();
+
+        // distinct encodings with their lengths and how many times they are written, where the
+        // values in a repeated one are not counted again as it is written once
+        let mut ids = BTreeMap::new();
+        let mut counts: Vec<(usize, usize)> = Vec::new();
+        let mut values = Vec::from_iter(spans.iter().map(|_| (None, 0)));
+        let mut pos = 0;
+        while let Some(&(start, end, after)) = spans.get(pos) {
+            let id = *ids.entry(&buf[start..end]).or_insert_with(|| {
+                counts.push((end - start, 0));
+                counts.len() - 1
+            });
+            counts[id].1 += 1;
+            values[pos] = (Some(id), after);
+            pos = if counts[id].1 > 1 { after } else { pos + 1 };
+        }
+
+        let mut writer = Writer::<O>::new();
+        writer.shared_values = values.into_iter().map(|(id, after)| {
+            let id = id.filter(|id| {
+                let (len, count) = counts[*id];
+                (count - 1) * len.saturating_sub(SHARED_REF_LEN) > SHARED_DEF_LEN
+            });
+            (id, after)
+        }).collect();
+        writer.shared = Vec::from_iter(counts.iter().map(|_| None));
+        writer.value(self);
+        writer.into_inner()
+    }
 }